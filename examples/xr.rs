@@ -4,21 +4,18 @@ use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 
 use bevy::prelude::*;
 use bevy::transform::components::Transform;
-use bevy_openxr::input::XrInput;
-use bevy_openxr::resources::{XrFrameState, XrInstance, XrSession};
 
+use bevy_openxr::xr_input::controllers::XrControllerInput;
 use bevy_openxr::xr_input::hand::{OpenXrHandInput, HandInputDebugRenderer};
 use bevy_openxr::xr_input::interactions::{
     draw_interaction_gizmos, draw_socket_gizmos, interactions, socket_interactions,
     update_interactable_states, InteractionEvent, Touched, XRDirectInteractor, XRInteractable,
     XRInteractableState, XRInteractorState, XRRayInteractor, XRSocketInteractor,
 };
-use bevy_openxr::xr_input::oculus_touch::OculusController;
 use bevy_openxr::xr_input::prototype_locomotion::{proto_locomotion, PrototypeLocomotionConfig};
 use bevy_openxr::xr_input::trackers::{
-    AimPose, OpenXRController, OpenXRLeftController, OpenXRRightController, OpenXRTracker,
+    OpenXRLeftController, OpenXRRightController, XrTrackerBundle, XrTrackerSettings,
 };
-use bevy_openxr::xr_input::Hand;
 use bevy_openxr::DefaultXrPlugins;
 
 fn main() {
@@ -33,6 +30,11 @@ fn main() {
         .add_systems(Startup, setup)
         .add_systems(Update, proto_locomotion)
         .insert_resource(PrototypeLocomotionConfig::default())
+        //the controllers get interactors, so spawn them ourselves
+        .insert_resource(XrTrackerSettings {
+            spawn_controllers: false,
+            ..default()
+        })
         .add_systems(Startup, spawn_controllers_example)
         .add_plugins(OpenXrHandInput)
         .add_plugins(HandInputDebugRenderer)
@@ -120,33 +122,21 @@ fn setup(
 fn spawn_controllers_example(mut commands: Commands) {
     //left hand
     commands.spawn((
-        OpenXRLeftController,
-        OpenXRController,
-        OpenXRTracker,
-        SpatialBundle::default(),
+        XrTrackerBundle::left_controller(),
         XRRayInteractor,
-        AimPose(Transform::default()),
         XRInteractorState::default(),
     ));
     //right hand
     commands.spawn((
-        OpenXRRightController,
-        OpenXRController,
-        OpenXRTracker,
-        SpatialBundle::default(),
+        XrTrackerBundle::right_controller(),
         XRDirectInteractor,
         XRInteractorState::default(),
     ));
 }
 
 fn prototype_interaction_input(
-    oculus_controller: Res<OculusController>,
-    frame_state: Res<XrFrameState>,
-    xr_input: Res<XrInput>,
-    instance: Res<XrInstance>,
-    session: Res<XrSession>,
     mut right_interactor_query: Query<
-        (&mut XRInteractorState),
+        (&mut XRInteractorState, &XrControllerInput),
         (
             With<XRDirectInteractor>,
             With<OpenXRRightController>,
@@ -154,7 +144,7 @@ fn prototype_interaction_input(
         ),
     >,
     mut left_interactor_query: Query<
-        (&mut XRInteractorState),
+        (&mut XRInteractorState, &XrControllerInput),
         (
             With<XRRayInteractor>,
             With<OpenXRLeftController>,
@@ -162,22 +152,15 @@ fn prototype_interaction_input(
         ),
     >,
 ) {
-    //lock frame
-    let frame_state = *frame_state.lock().unwrap();
-    //get controller
-    let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
-    //get controller triggers
-    let left_trigger = controller.trigger(Hand::Left);
-    let right_trigger = controller.trigger(Hand::Right);
     //get the interactors and do state stuff
-    let mut left_state = left_interactor_query.single_mut();
-    if left_trigger > 0.8 {
+    let (mut left_state, left_controller) = left_interactor_query.single_mut();
+    if left_controller.trigger > 0.8 {
         *left_state = XRInteractorState::Selecting;
     } else {
         *left_state = XRInteractorState::Idle;
     }
-    let mut right_state = right_interactor_query.single_mut();
-    if right_trigger > 0.8 {
+    let (mut right_state, right_controller) = right_interactor_query.single_mut();
+    if right_controller.trigger > 0.8 {
         *right_state = XRInteractorState::Selecting;
     } else {
         *right_state = XRInteractorState::Idle;
@@ -51,6 +51,32 @@ pub fn initialize_xr_graphics(
 
     let mut enabled_extensions = xr::ExtensionSet::default();
     enabled_extensions.khr_vulkan_enable2 = true;
+    //interaction profiles that are only available through an extension
+    enabled_extensions.ext_hp_mixed_reality_controller =
+        available_extensions.ext_hp_mixed_reality_controller;
+    enabled_extensions.bd_controller_interaction = available_extensions.bd_controller_interaction;
+    //these are newer than the bindings, so they are enabled by name
+    for extension in [
+        "XR_EXT_hand_interaction",
+        "XR_EXT_hand_tracking_data_source",
+    ] {
+        if available_extensions
+            .other
+            .iter()
+            .any(|other| other == extension)
+        {
+            enabled_extensions.other.push(extension.to_string());
+        }
+    }
+    enabled_extensions.ext_palm_pose = available_extensions.ext_palm_pose;
+    enabled_extensions.fb_haptic_pcm = available_extensions.fb_haptic_pcm;
+    enabled_extensions.ext_hand_tracking = available_extensions.ext_hand_tracking;
+    enabled_extensions.fb_hand_tracking_mesh = available_extensions.fb_hand_tracking_mesh;
+    enabled_extensions.fb_hand_tracking_aim = available_extensions.fb_hand_tracking_aim;
+    enabled_extensions.ext_hand_joints_motion_range =
+        available_extensions.ext_hand_joints_motion_range;
+    enabled_extensions.fb_touch_controller_proximity =
+        available_extensions.fb_touch_controller_proximity;
     #[cfg(target_os = "android")]
     {
         enabled_extensions.khr_android_create_instance = true;
@@ -19,8 +19,10 @@ use bevy::window::{PresentMode, PrimaryWindow, RawHandleWrapper};
 use input::XrInput;
 use openxr as xr;
 use resources::*;
-use xr_input::controllers::XrControllerType;
-use xr_input::OpenXrInput;
+use xr_input::controllers::{
+    current_interaction_profile, XrInteractionProfile, XrInteractionProfileChanged,
+};
+use xr_input::{Hand, OpenXrInput};
 
 const VIEW_TYPE: xr::ViewConfigurationType = xr::ViewConfigurationType::PRIMARY_STEREO;
 
@@ -96,6 +98,8 @@ impl Plugin for OpenXrPlugin {
             frame_state,
         ));
         app.insert_resource(ActionSets(vec![]));
+        app.init_resource::<XrInteractionProfile>()
+            .add_event::<XrInteractionProfileChanged>();
         app.add_plugins(RenderPlugin {
             render_creation: RenderCreation::Manual(
                 device,
@@ -199,7 +203,7 @@ impl PluginGroup for DefaultXrPlugins {
             .disable::<RenderPlugin>()
             .disable::<PipelinedRenderingPlugin>()
             .add_before::<RenderPlugin, _>(OpenXrPlugin)
-            .add_after::<OpenXrPlugin, _>(OpenXrInput::new(XrControllerType::OculusTouch))
+            .add_after::<OpenXrPlugin, _>(OpenXrInput)
             .set(WindowPlugin {
                 #[cfg(not(target_os = "android"))]
                 primary_window: Some(Window {
@@ -226,6 +230,8 @@ pub fn xr_begin_frame(
     swapchain: Res<XrSwapchain>,
     views: Res<XrViews>,
     input: Res<XrInput>,
+    mut interaction_profile: ResMut<XrInteractionProfile>,
+    mut interaction_profile_changed: EventWriter<XrInteractionProfileChanged>,
 ) {
     {
         let _span = info_span!("xr_poll_events");
@@ -249,6 +255,23 @@ pub fn xr_begin_frame(
                         _ => {}
                     }
                 }
+                InteractionProfileChanged(_) => {
+                    for hand in [Hand::Left, Hand::Right] {
+                        let profile = match current_interaction_profile(&session, hand) {
+                            Ok(profile) => profile,
+                            Err(err) => {
+                                warn!("failed to get the {:?} interaction profile: {}", hand, err);
+                                continue;
+                            }
+                        };
+                        if interaction_profile.get(hand) != profile.as_deref() {
+                            info!("{:?} interaction profile changed to {:?}", hand, profile);
+                            interaction_profile.set(hand, profile.clone());
+                            interaction_profile_changed
+                                .send(XrInteractionProfileChanged { hand, profile });
+                        }
+                    }
+                }
                 InstanceLossPending(_) => return,
                 EventsLost(e) => {
                     warn!("lost {} XR events", e.lost_event_count());
@@ -273,14 +296,29 @@ pub fn xr_begin_frame(
     }
     {
         let _span = info_span!("xr_locate_views").entered();
-        *views.lock().unwrap() = session
+        let (view_state, new_views) = session
             .locate_views(
                 VIEW_TYPE,
                 frame_state.lock().unwrap().predicted_display_time,
                 &input.stage,
             )
-            .unwrap()
-            .1;
+            .unwrap();
+        //keep the last good pose while the headset isn't tracked so the views don't snap to the
+        //origin
+        let mut views = views.lock().unwrap();
+        if views.len() == new_views.len() {
+            for (view, new_view) in views.iter_mut().zip(new_views) {
+                view.fov = new_view.fov;
+                if view_state.contains(xr::ViewStateFlags::POSITION_VALID) {
+                    view.pose.position = new_view.pose.position;
+                }
+                if view_state.contains(xr::ViewStateFlags::ORIENTATION_VALID) {
+                    view.pose.orientation = new_view.pose.orientation;
+                }
+            }
+        } else {
+            *views = new_views;
+        }
     }
 }
 
@@ -0,0 +1,391 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use bevy::prelude::*;
+use openxr::{Action, ActionSet, ActionTy, Binding, Instance, Path};
+
+use crate::resources::{XrInstance, XrSession};
+
+use super::oculus_touch::{init_subaction_path, ActionSets, OculusController};
+
+/// Creates the OpenXR actions declared with [`XrActionsAppExt::add_xr_action_set`], together with
+/// the built in controller actions, and attaches them to the session at startup.
+///
+/// This is added by `OpenXrInput`, adding it again before or after that plugin does nothing.
+///
+/// ```ignore
+/// struct Jump;
+///
+/// impl XrActionMarker for Jump {
+///     type Value = bool;
+///     const NAME: &'static str = "jump";
+///     const LOCALIZED_NAME: &'static str = "Jump";
+/// }
+///
+/// app.add_xr_action_set(XrActionSetDesc::new("gameplay", "Gameplay", 0).with_action(
+///     XrActionDesc::<Jump>::new().binding(
+///         "/interaction_profiles/oculus/touch_controller",
+///         "/user/hand/right/input/a/click",
+///     ),
+/// ));
+///
+/// fn jump(jump: Res<XrAction<Jump>>, session: Res<XrSession>) {
+///     if jump.state(&session, Path::NULL).unwrap().current_state {
+///         // ...
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct XrActionsPlugin;
+
+impl Plugin for XrActionsPlugin {
+    fn build(&self, app: &mut App) {
+        //while building, the plugin itself isn't registered yet
+        if app.is_plugin_added::<Self>() {
+            return;
+        }
+        app.init_resource::<XrActionDeclarations>()
+            .add_systems(Startup, setup_xr_actions);
+    }
+
+    //`OpenXrInput` adds it too, so apps can add it in any order
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+/// inserted by `OpenXrInput` to create the `OculusController` actions for every
+/// `XrControllerType`
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct XrControllerActions;
+
+pub trait XrActionsAppExt {
+    fn add_xr_action_set(&mut self, action_set: XrActionSetDesc) -> &mut Self;
+}
+
+impl XrActionsAppExt for App {
+    fn add_xr_action_set(&mut self, action_set: XrActionSetDesc) -> &mut Self {
+        self.init_resource::<XrActionDeclarations>();
+        self.world
+            .resource_mut::<XrActionDeclarations>()
+            .action_sets
+            .push(action_set);
+        self
+    }
+}
+
+/// implemented by marker types naming a user declared action, read the action with
+/// `Res<XrAction<Marker>>`
+pub trait XrActionMarker: Send + Sync + 'static {
+    /// one of `bool`, `f32`, `openxr::Vector2f`, `openxr::Posef` or `openxr::Haptic`
+    type Value: ActionTy + Send + Sync + 'static;
+    const NAME: &'static str;
+    const LOCALIZED_NAME: &'static str;
+}
+
+/// a created action, derefs to the underlying `openxr::Action`
+#[derive(Resource)]
+pub struct XrAction<M: XrActionMarker> {
+    action: Action<M::Value>,
+    subaction_paths: Vec<Path>,
+    _marker: PhantomData<M>,
+}
+
+impl<M: XrActionMarker> XrAction<M> {
+    pub fn subaction_paths(&self) -> &[Path] {
+        &self.subaction_paths
+    }
+}
+
+impl<M: XrActionMarker> Deref for XrAction<M> {
+    type Target = Action<M::Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.action
+    }
+}
+
+pub struct XrActionSetDesc {
+    pub name: String,
+    pub localized_name: String,
+    pub priority: u32,
+    actions: Vec<Box<dyn XrActionDeclaration>>,
+}
+
+impl XrActionSetDesc {
+    pub fn new(name: impl Into<String>, localized_name: impl Into<String>, priority: u32) -> Self {
+        Self {
+            name: name.into(),
+            localized_name: localized_name.into(),
+            priority,
+            actions: vec![],
+        }
+    }
+
+    pub fn with_action<M: XrActionMarker>(mut self, action: XrActionDesc<M>) -> Self {
+        self.actions.push(Box::new(action));
+        self
+    }
+}
+
+pub struct XrActionDesc<M: XrActionMarker> {
+    pub subaction_paths: Vec<String>,
+    /// interaction profile and binding path pairs
+    pub bindings: Vec<(String, String)>,
+    _marker: PhantomData<M>,
+}
+
+impl<M: XrActionMarker> Default for XrActionDesc<M> {
+    fn default() -> Self {
+        Self {
+            subaction_paths: vec![],
+            bindings: vec![],
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<M: XrActionMarker> XrActionDesc<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subaction_path(mut self, path: impl Into<String>) -> Self {
+        self.subaction_paths.push(path.into());
+        self
+    }
+
+    /// use `/user/hand/left` and `/user/hand/right` as subaction paths so the action can be read
+    /// per hand with `subaction_path(hand)`
+    pub fn for_hands(self) -> Self {
+        self.subaction_path("/user/hand/left")
+            .subaction_path("/user/hand/right")
+    }
+
+    pub fn binding(
+        mut self,
+        interaction_profile: impl Into<String>,
+        path: impl Into<String>,
+    ) -> Self {
+        self.bindings
+            .push((interaction_profile.into(), path.into()));
+        self
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct XrActionDeclarations {
+    pub action_sets: Vec<XrActionSetDesc>,
+}
+
+/// the spec only keeps the last suggestion for every interaction profile, so all bindings are
+/// gathered here first and suggested once per profile
+#[derive(Default)]
+pub struct SuggestedBindings<'a> {
+    profiles: Vec<(Path, Vec<Binding<'a>>)>,
+}
+
+impl<'a> SuggestedBindings<'a> {
+    pub fn add<T: ActionTy>(
+        &mut self,
+        instance: &Instance,
+        interaction_profile: &str,
+        action: &'a Action<T>,
+        path: &str,
+    ) -> openxr::Result<()> {
+        self.profile(instance, interaction_profile)?
+            .add(action, path)
+    }
+
+    /// returns a helper that adds bindings to a single profile
+    pub fn profile<'b>(
+        &'b mut self,
+        instance: &'b Instance,
+        interaction_profile: &str,
+    ) -> openxr::Result<ProfileBindings<'a, 'b>> {
+        let profile = instance.string_to_path(interaction_profile)?;
+        let index = match self.profiles.iter().position(|(p, _)| *p == profile) {
+            Some(index) => index,
+            None => {
+                self.profiles.push((profile, vec![]));
+                self.profiles.len() - 1
+            }
+        };
+        Ok(ProfileBindings {
+            instance,
+            bindings: &mut self.profiles[index].1,
+        })
+    }
+
+    /// runtimes reject profiles they don't know, that shouldn't stop the other profiles from
+    /// being suggested so those errors are only logged
+    pub fn suggest(&self, instance: &Instance) {
+        for (profile, bindings) in self.profiles.iter() {
+            if let Err(err) = instance.suggest_interaction_profile_bindings(*profile, bindings) {
+                warn!(
+                    "failed to suggest bindings for {}: {}",
+                    instance
+                        .path_to_string(*profile)
+                        .unwrap_or_else(|_| "unknown profile".into()),
+                    err
+                );
+            }
+        }
+    }
+}
+
+pub struct ProfileBindings<'a, 'b> {
+    instance: &'b Instance,
+    bindings: &'b mut Vec<Binding<'a>>,
+}
+
+impl<'a, 'b> ProfileBindings<'a, 'b> {
+    pub fn add<T: ActionTy>(&mut self, action: &'a Action<T>, path: &str) -> openxr::Result<()> {
+        let binding = Binding::new(action, self.instance.string_to_path(path)?);
+        self.bindings.push(binding);
+        Ok(())
+    }
+
+    /// binds `path` relative to `/user/hand/left/`
+    pub fn left<T: ActionTy>(&mut self, action: &'a Action<T>, path: &str) -> openxr::Result<()> {
+        self.add(action, &format!("/user/hand/left/{}", path))
+    }
+
+    /// binds `path` relative to `/user/hand/right/`
+    pub fn right<T: ActionTy>(&mut self, action: &'a Action<T>, path: &str) -> openxr::Result<()> {
+        self.add(action, &format!("/user/hand/right/{}", path))
+    }
+
+    /// binds `path` on both hands
+    pub fn hands<T: ActionTy>(&mut self, action: &'a Action<T>, path: &str) -> openxr::Result<()> {
+        self.left(action, path)?;
+        self.right(action, path)
+    }
+}
+
+trait XrActionDeclaration: Send + Sync {
+    fn create(
+        &self,
+        instance: &Instance,
+        action_set: &ActionSet,
+    ) -> anyhow::Result<Box<dyn CreatedXrAction>>;
+}
+
+trait CreatedXrAction {
+    fn suggest_bindings<'a>(
+        &'a self,
+        instance: &Instance,
+        bindings: &mut SuggestedBindings<'a>,
+    ) -> anyhow::Result<()>;
+
+    fn insert(self: Box<Self>, world: &mut World);
+}
+
+impl<M: XrActionMarker> XrActionDeclaration for XrActionDesc<M> {
+    fn create(
+        &self,
+        instance: &Instance,
+        action_set: &ActionSet,
+    ) -> anyhow::Result<Box<dyn CreatedXrAction>> {
+        let subaction_paths = self
+            .subaction_paths
+            .iter()
+            .map(|path| instance.string_to_path(path))
+            .collect::<openxr::Result<Vec<_>>>()?;
+        let action =
+            action_set.create_action::<M::Value>(M::NAME, M::LOCALIZED_NAME, &subaction_paths)?;
+        Ok(Box::new(CreatedAction {
+            action: XrAction::<M> {
+                action,
+                subaction_paths,
+                _marker: PhantomData,
+            },
+            bindings: self.bindings.clone(),
+        }))
+    }
+}
+
+struct CreatedAction<M: XrActionMarker> {
+    action: XrAction<M>,
+    bindings: Vec<(String, String)>,
+}
+
+impl<M: XrActionMarker> CreatedXrAction for CreatedAction<M> {
+    fn suggest_bindings<'a>(
+        &'a self,
+        instance: &Instance,
+        bindings: &mut SuggestedBindings<'a>,
+    ) -> anyhow::Result<()> {
+        for (profile, path) in self.bindings.iter() {
+            bindings.add(instance, profile, &self.action.action, path)?;
+        }
+        Ok(())
+    }
+
+    fn insert(self: Box<Self>, world: &mut World) {
+        world.insert_resource(self.action);
+    }
+}
+
+/// a failure leaves the app without `OculusController` and user actions, the systems using them
+/// don't run
+pub fn setup_xr_actions(world: &mut World) {
+    if let Err(err) = create_xr_actions(world) {
+        error!(
+            "failed to set up xr actions, controller input is disabled: {}",
+            err
+        );
+    }
+}
+
+fn create_xr_actions(world: &mut World) -> anyhow::Result<()> {
+    let instance = world.resource::<XrInstance>().clone();
+    let session = world.resource::<XrSession>().clone();
+    let declarations = std::mem::take(
+        &mut world
+            .get_resource_or_insert_with(XrActionDeclarations::default)
+            .action_sets,
+    );
+    init_subaction_path(&instance);
+
+    let mut action_sets = vec![];
+    let oculus_controller = if world.contains_resource::<XrControllerActions>() {
+        Some(OculusController::new(
+            Instance::clone(&instance),
+            openxr::Session::clone(&session),
+            &mut action_sets,
+        )?)
+    } else {
+        None
+    };
+    let mut actions = vec![];
+    for set in declarations.iter() {
+        let action_set =
+            instance.create_action_set(&set.name, &set.localized_name, set.priority)?;
+        for action in set.actions.iter() {
+            actions.push(action.create(&instance, &action_set)?);
+        }
+        action_sets.push(action_set);
+    }
+
+    {
+        let mut bindings = SuggestedBindings::default();
+        if let Some(controller) = &oculus_controller {
+            controller.suggest_bindings(&instance, &mut bindings)?;
+        }
+        for action in actions.iter() {
+            action.suggest_bindings(&instance, &mut bindings)?;
+        }
+        bindings.suggest(&instance);
+    }
+    session.attach_action_sets(&action_sets.iter().collect::<Vec<_>>())?;
+
+    if let Some(controller) = oculus_controller {
+        world.insert_resource(controller);
+    }
+    for action in actions {
+        action.insert(world);
+    }
+    world.insert_resource(ActionSets(action_sets));
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+
+use super::{
+    controllers::{XrControllerInput, XrControllerInputSet},
+    trackers::{OpenXRLeftController, OpenXRRightController},
+    Hand,
+};
+
+/// Fills `Input<XrButton>` and `Axis<XrAxis>` from the controllers' `XrControllerInput`.
+///
+/// This is added by `OpenXrInput` and `XrSimulatorPlugin`.
+#[derive(Default)]
+pub struct XrButtonsPlugin;
+
+impl Plugin for XrButtonsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Input<XrButton>>()
+            .init_resource::<Axis<XrAxis>>()
+            .init_resource::<XrButtonSettings>()
+            .add_systems(PreUpdate, update_xr_buttons.after(XrControllerInputSet));
+    }
+}
+
+/// a controller button, read it with `Res<Input<XrButton>>` like keyboard and gamepad buttons
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct XrButton {
+    pub hand: Hand,
+    pub button_type: XrButtonType,
+}
+
+impl XrButton {
+    pub fn new(hand: Hand, button_type: XrButtonType) -> Self {
+        Self { hand, button_type }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum XrButtonType {
+    /// pressed past `XrButtonSettings::press_threshold`
+    Trigger,
+    TriggerTouch,
+    /// pressed past `XrButtonSettings::press_threshold`
+    Squeeze,
+    /// the A button on the right controller and the X button on the left one
+    Primary,
+    PrimaryTouch,
+    /// the B button on the right controller and the Y button on the left one
+    Secondary,
+    SecondaryTouch,
+    /// only on the left controller
+    Menu,
+    Thumbstick,
+    ThumbstickTouch,
+    ThumbrestTouch,
+}
+
+impl XrButtonType {
+    pub const ALL: [XrButtonType; 11] = [
+        XrButtonType::Trigger,
+        XrButtonType::TriggerTouch,
+        XrButtonType::Squeeze,
+        XrButtonType::Primary,
+        XrButtonType::PrimaryTouch,
+        XrButtonType::Secondary,
+        XrButtonType::SecondaryTouch,
+        XrButtonType::Menu,
+        XrButtonType::Thumbstick,
+        XrButtonType::ThumbstickTouch,
+        XrButtonType::ThumbrestTouch,
+    ];
+}
+
+/// a controller axis, read it with `Res<Axis<XrAxis>>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct XrAxis {
+    pub hand: Hand,
+    pub axis_type: XrAxisType,
+}
+
+impl XrAxis {
+    pub fn new(hand: Hand, axis_type: XrAxisType) -> Self {
+        Self { hand, axis_type }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum XrAxisType {
+    /// 0.0 to 1.0
+    Trigger,
+    /// 0.0 to 1.0
+    Squeeze,
+    ThumbstickX,
+    ThumbstickY,
+}
+
+/// when the analog trigger and squeeze count as pressed, the release threshold is lower so a
+/// value hovering around the threshold doesn't toggle every frame
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct XrButtonSettings {
+    pub press_threshold: f32,
+    pub release_threshold: f32,
+}
+
+impl Default for XrButtonSettings {
+    fn default() -> Self {
+        Self {
+            press_threshold: 0.75,
+            release_threshold: 0.65,
+        }
+    }
+}
+
+impl XrButtonSettings {
+    pub fn is_pressed(&self, value: f32, was_pressed: bool) -> bool {
+        if was_pressed {
+            value > self.release_threshold
+        } else {
+            value >= self.press_threshold
+        }
+    }
+}
+
+/// presses or releases `button`, only touching the input when the state changed so
+/// `just_pressed` and `just_released` stay accurate
+pub fn set_xr_button(input: &mut Input<XrButton>, button: XrButton, pressed: bool) {
+    match (pressed, input.pressed(button)) {
+        (true, false) => input.press(button),
+        (false, true) => input.release(button),
+        _ => {}
+    }
+}
+
+/// sets an analog button and its axis using the thresholds from `settings`
+pub fn set_xr_analog(
+    input: &mut Input<XrButton>,
+    axis: &mut Axis<XrAxis>,
+    settings: &XrButtonSettings,
+    button: XrButton,
+    axis_type: XrAxisType,
+    value: f32,
+) {
+    axis.set(XrAxis::new(button.hand, axis_type), value);
+    let pressed = settings.is_pressed(value, input.pressed(button));
+    set_xr_button(input, button, pressed);
+}
+
+/// updates `Input<XrButton>` and `Axis<XrAxis>` from the `XrControllerInput` of the controllers,
+/// so headset, simulated and replayed input reach them the same way
+pub fn update_xr_buttons(
+    mut input: ResMut<Input<XrButton>>,
+    mut axis: ResMut<Axis<XrAxis>>,
+    settings: Res<XrButtonSettings>,
+    controller_query: Query<(
+        &XrControllerInput,
+        Has<OpenXRLeftController>,
+        Has<OpenXRRightController>,
+    )>,
+) {
+    input.bypass_change_detection().clear();
+    for hand in [Hand::Left, Hand::Right] {
+        //every tracker of a hand has the same input, a hand without one is released
+        let controller = controller_query
+            .iter()
+            .find(|(_, left, right)| match hand {
+                Hand::Left => *left,
+                Hand::Right => *right,
+            })
+            .map(|(controller, ..)| *controller)
+            .unwrap_or_default();
+        for button_type in XrButtonType::ALL {
+            let pressed = match button_type {
+                XrButtonType::Trigger | XrButtonType::Squeeze => continue,
+                XrButtonType::TriggerTouch => controller.trigger_touched,
+                XrButtonType::Primary => controller.primary_button,
+                XrButtonType::PrimaryTouch => controller.primary_touched,
+                XrButtonType::Secondary => controller.secondary_button,
+                XrButtonType::SecondaryTouch => controller.secondary_touched,
+                XrButtonType::Menu => controller.menu_button,
+                XrButtonType::Thumbstick => controller.thumbstick_click,
+                XrButtonType::ThumbstickTouch => controller.thumbstick_touched,
+                XrButtonType::ThumbrestTouch => controller.thumbrest_touched,
+            };
+            set_xr_button(&mut input, XrButton::new(hand, button_type), pressed);
+        }
+        set_xr_analog(
+            &mut input,
+            &mut axis,
+            &settings,
+            XrButton::new(hand, XrButtonType::Trigger),
+            XrAxisType::Trigger,
+            controller.trigger,
+        );
+        set_xr_analog(
+            &mut input,
+            &mut axis,
+            &settings,
+            XrButton::new(hand, XrButtonType::Squeeze),
+            XrAxisType::Squeeze,
+            controller.squeeze,
+        );
+        axis.set(
+            XrAxis::new(hand, XrAxisType::ThumbstickX),
+            controller.thumbstick.x,
+        );
+        axis.set(
+            XrAxis::new(hand, XrAxisType::ThumbstickY),
+            controller.thumbstick.y,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_and_release_have_hysteresis() {
+        let settings = XrButtonSettings::default();
+        //rising, only the press threshold counts
+        assert!(!settings.is_pressed(0.7, false));
+        assert!(settings.is_pressed(0.75, false));
+        //falling, stays pressed until the release threshold
+        assert!(settings.is_pressed(0.7, true));
+        assert!(!settings.is_pressed(0.65, true));
+        assert!(!settings.is_pressed(0.0, true));
+        assert!(settings.is_pressed(1.0, true));
+    }
+
+    #[test]
+    fn analog_button_only_changes_on_crossing() {
+        let settings = XrButtonSettings::default();
+        let mut input = Input::<XrButton>::default();
+        let mut axis = Axis::<XrAxis>::default();
+        let button = XrButton::new(Hand::Right, XrButtonType::Trigger);
+        let mut set = |input: &mut Input<XrButton>, value: f32| {
+            input.clear();
+            set_xr_analog(
+                input,
+                &mut axis,
+                &settings,
+                button,
+                XrAxisType::Trigger,
+                value,
+            );
+        };
+        set(&mut input, 0.8);
+        assert!(input.just_pressed(button));
+        set(&mut input, 0.7);
+        assert!(input.pressed(button) && !input.just_pressed(button));
+        set(&mut input, 0.6);
+        assert!(input.just_released(button));
+        set(&mut input, 0.7);
+        assert!(!input.pressed(button) && !input.just_released(button));
+    }
+}
@@ -1,4 +1,9 @@
-use openxr::{Action, ActionTy};
+use bevy::prelude::{Component, Event, Resource, SystemSet, Vec2};
+use openxr::{Action, ActionTy, AnyGraphics, Path, Session};
+
+use super::hand::{ButtonState, HandCurlSettings, HandState, ThumbstickState, TriggerState};
+use super::oculus_touch::{subaction_path, OculusControllerRef};
+use super::Hand;
 
 pub struct Touchable<T: ActionTy> {
     pub inner: Action<T>,
@@ -8,7 +13,231 @@ pub struct Handed<T> {
     pub left: T,
     pub right: T,
 }
-#[derive(Copy, Clone)]
+/// the controllers bindings are suggested for, all of them are mapped onto the actions exposed
+/// by `OculusControllerRef`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Resource)]
 pub enum XrControllerType {
     OculusTouch,
+    SimpleController,
+    ValveIndex,
+    HtcVive,
+    WindowsMixedReality,
+    Pico4,
+    HpReverbG2,
+    /// hand tracking through `XR_EXT_hand_interaction`, pinch selects and grasp squeezes
+    HandInteraction,
+}
+
+impl XrControllerType {
+    pub const ALL: [XrControllerType; 8] = [
+        XrControllerType::OculusTouch,
+        XrControllerType::SimpleController,
+        XrControllerType::ValveIndex,
+        XrControllerType::HtcVive,
+        XrControllerType::WindowsMixedReality,
+        XrControllerType::Pico4,
+        XrControllerType::HpReverbG2,
+        XrControllerType::HandInteraction,
+    ];
+
+    pub fn interaction_profile(&self) -> &'static str {
+        match self {
+            XrControllerType::OculusTouch => "/interaction_profiles/oculus/touch_controller",
+            XrControllerType::SimpleController => "/interaction_profiles/khr/simple_controller",
+            XrControllerType::ValveIndex => "/interaction_profiles/valve/index_controller",
+            XrControllerType::HtcVive => "/interaction_profiles/htc/vive_controller",
+            XrControllerType::WindowsMixedReality => {
+                "/interaction_profiles/microsoft/motion_controller"
+            }
+            XrControllerType::Pico4 => "/interaction_profiles/bytedance/pico4_controller",
+            XrControllerType::HpReverbG2 => "/interaction_profiles/hp/mixed_reality_controller",
+            XrControllerType::HandInteraction => "/interaction_profiles/ext/hand_interaction_ext",
+        }
+    }
+
+    pub fn from_interaction_profile(profile: &str) -> Option<XrControllerType> {
+        XrControllerType::ALL
+            .into_iter()
+            .find(|controller| controller.interaction_profile() == profile)
+    }
+}
+
+/// the interaction profile the runtime currently uses for each hand, `None` while the runtime
+/// hasn't picked one
+#[derive(Clone, Debug, Default, PartialEq, Eq, Resource)]
+pub struct XrInteractionProfile {
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl XrInteractionProfile {
+    pub fn get(&self, hand: Hand) -> Option<&str> {
+        match hand {
+            Hand::Left => self.left.as_deref(),
+            Hand::Right => self.right.as_deref(),
+        }
+    }
+
+    pub fn set(&mut self, hand: Hand, profile: Option<String>) {
+        match hand {
+            Hand::Left => self.left = profile,
+            Hand::Right => self.right = profile,
+        }
+    }
+
+    /// the controller type of the current profile, `None` for profiles we don't suggest bindings for
+    pub fn controller_type(&self, hand: Hand) -> Option<XrControllerType> {
+        XrControllerType::from_interaction_profile(self.get(hand)?)
+    }
+}
+
+/// sent when the interaction profile of a hand changes, e.g. when the user picks up different
+/// controllers
+#[derive(Clone, Debug, PartialEq, Eq, Event)]
+pub struct XrInteractionProfileChanged {
+    pub hand: Hand,
+    pub profile: Option<String>,
+}
+
+pub fn current_interaction_profile(
+    session: &Session<AnyGraphics>,
+    hand: Hand,
+) -> openxr::Result<Option<String>> {
+    let profile = session.current_interaction_profile(subaction_path(hand))?;
+    if profile == Path::NULL {
+        Ok(None)
+    } else {
+        Ok(Some(session.instance().path_to_string(profile)?))
+    }
+}
+
+/// the systems writing `XrControllerInput`, order systems reading it after this set
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct XrControllerInputSet;
+
+/// the input of a controller, cached once per frame on the `OpenXRLeftController` and
+/// `OpenXRRightController` entities
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct XrControllerInput {
+    pub trigger: f32,
+    pub trigger_touched: bool,
+    pub squeeze: f32,
+    /// the A button on the right controller and the X button on the left one
+    pub primary_button: bool,
+    pub primary_touched: bool,
+    /// the B button on the right controller and the Y button on the left one
+    pub secondary_button: bool,
+    pub secondary_touched: bool,
+    /// only on the left controller
+    pub menu_button: bool,
+    pub thumbstick: Vec2,
+    pub thumbstick_click: bool,
+    pub thumbstick_touched: bool,
+    pub thumbrest_touched: bool,
+    /// whether a finger is near the trigger, `None` without `XR_FB_touch_controller_proximity`
+    pub trigger_proximity: Option<bool>,
+    /// whether the thumb is near the thumbrest, the stick or the buttons, `None` without
+    /// `XR_FB_touch_controller_proximity`
+    pub thumb_proximity: Option<bool>,
+}
+
+impl XrControllerInput {
+    pub fn from_controller(controller: &OculusControllerRef, hand: Hand) -> Self {
+        let (primary_button, primary_touched, secondary_button, secondary_touched, menu_button) =
+            match hand {
+                Hand::Left => (
+                    controller.x_button(),
+                    controller.x_button_touched(),
+                    controller.y_button(),
+                    controller.y_button_touched(),
+                    controller.menu_button(),
+                ),
+                Hand::Right => (
+                    controller.a_button(),
+                    controller.a_button_touched(),
+                    controller.b_button(),
+                    controller.b_button_touched(),
+                    false,
+                ),
+            };
+        let thumbstick = controller.thumbstick(hand);
+        Self {
+            trigger: controller.trigger(hand),
+            trigger_touched: controller.trigger_touched(hand),
+            squeeze: controller.squeeze(hand),
+            primary_button,
+            primary_touched,
+            secondary_button,
+            secondary_touched,
+            menu_button,
+            thumbstick: Vec2::new(thumbstick.x, thumbstick.y),
+            thumbstick_click: thumbstick.click,
+            thumbstick_touched: controller.thumbstick_touch(hand),
+            thumbrest_touched: controller.thumbrest_touch(hand),
+            trigger_proximity: controller.trigger_proximity(hand),
+            thumb_proximity: controller.thumb_proximity(hand),
+        }
+    }
+
+    /// the hand state with the unsmoothed finger curl of the default `HandCurlSettings`
+    pub fn hand_state(&self) -> HandState {
+        let button_state = |pressed: bool, touched: bool| match (pressed, touched) {
+            (true, _) => ButtonState::PRESSED,
+            (false, true) => ButtonState::TOUCHED,
+            (false, false) => ButtonState::OFF,
+        };
+        HandState {
+            grip: self.squeeze,
+            trigger_state: match self.trigger_touched {
+                true => match self.trigger > 0.0 {
+                    true => TriggerState::PULLED,
+                    false => TriggerState::TOUCHED,
+                },
+                false => TriggerState::OFF,
+            },
+            a_button: button_state(self.primary_button, self.primary_touched),
+            b_button: button_state(self.secondary_button, self.secondary_touched),
+            thumbstick: match self.thumbstick_touched {
+                true => match self.thumbstick != Vec2::ZERO {
+                    true => ThumbstickState::PRESSED,
+                    false => ThumbstickState::TOUCHED,
+                },
+                false => ThumbstickState::OFF,
+            },
+            curl: HandCurlSettings::default().target_curl(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumbstick_is_pressed_in_every_direction() {
+        for thumbstick in [
+            Vec2::new(1.0, 0.0),
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, -1.0),
+        ] {
+            let input = XrControllerInput {
+                thumbstick,
+                thumbstick_touched: true,
+                ..Default::default()
+            };
+            assert!(matches!(
+                input.hand_state().thumbstick,
+                ThumbstickState::PRESSED
+            ));
+        }
+        let resting = XrControllerInput {
+            thumbstick_touched: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            resting.hand_state().thumbstick,
+            ThumbstickState::TOUCHED
+        ));
+    }
 }
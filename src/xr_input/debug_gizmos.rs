@@ -1,17 +1,9 @@
 use bevy::prelude::{
-    info, Color, Gizmos, GlobalTransform, Plugin, Quat, Query, Res, Transform, Update, Vec2, Vec3,
-    With, Without,
+    info, Color, Gizmos, GlobalTransform, Plugin, Quat, Query, Transform, Update, Vec2, Vec3, With,
+    Without,
 };
 
-use crate::{
-    input::XrInput,
-    resources::{XrFrameState, XrInstance, XrSession},
-};
-
-use crate::xr_input::{
-    oculus_touch::{OculusController, OculusControllerRef},
-    Hand,
-};
+use crate::xr_input::{controllers::XrControllerInput, Hand};
 
 use super::trackers::{OpenXRLeftController, OpenXRRightController, OpenXRTrackingRoot};
 
@@ -27,29 +19,22 @@ impl Plugin for OpenXrDebugRenderer {
 
 pub fn draw_gizmos(
     mut gizmos: Gizmos,
-    oculus_controller: Res<OculusController>,
-    frame_state: Res<XrFrameState>,
-    xr_input: Res<XrInput>,
-    instance: Res<XrInstance>,
-    session: Res<XrSession>,
     tracking_root_query: Query<(&mut Transform, With<OpenXRTrackingRoot>)>,
     left_controller_query: Query<(
         &GlobalTransform,
+        &XrControllerInput,
         With<OpenXRLeftController>,
         Without<OpenXRRightController>,
         Without<OpenXRTrackingRoot>,
     )>,
     right_controller_query: Query<(
         &GlobalTransform,
+        &XrControllerInput,
         With<OpenXRRightController>,
         Without<OpenXRLeftController>,
         Without<OpenXRTrackingRoot>,
     )>,
 ) {
-    //lock frame
-    let frame_state = *frame_state.lock().unwrap();
-    //get controller
-    let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
     //tracking root?
     let mut tracking_transform = &Transform::IDENTITY;
     let root = tracking_root_query.get_single();
@@ -71,16 +56,17 @@ pub fn draw_gizmos(
         Err(_) => info!("too many tracking roots"),
     }
     //draw the hands
-    let left_transform = left_controller_query.get_single().unwrap().0;
-    let right_transform = right_controller_query.get_single().unwrap().0;
-
-    draw_hand_gizmo(&mut gizmos, &controller, Hand::Right, right_transform);
-    draw_hand_gizmo(&mut gizmos, &controller, Hand::Left, left_transform);
+    if let Ok((right_transform, right_controller, ..)) = right_controller_query.get_single() {
+        draw_hand_gizmo(&mut gizmos, right_controller, Hand::Right, right_transform);
+    }
+    if let Ok((left_transform, left_controller, ..)) = left_controller_query.get_single() {
+        draw_hand_gizmo(&mut gizmos, left_controller, Hand::Left, left_transform);
+    }
 }
 
 fn draw_hand_gizmo(
     gizmos: &mut Gizmos,
-    controller: &OculusControllerRef<'_>,
+    controller: &XrControllerInput,
     hand: Hand,
     hand_transform: &GlobalTransform,
 ) {
@@ -123,10 +109,10 @@ fn draw_hand_gizmo(
 
             //button b
             let mut b_color = off_color;
-            if controller.y_button_touched() {
+            if controller.secondary_touched {
                 b_color = touch_color;
             }
-            if controller.y_button() {
+            if controller.secondary_button {
                 b_color = pressed_color;
             }
 
@@ -137,10 +123,10 @@ fn draw_hand_gizmo(
 
             //button a
             let mut a_color = off_color;
-            if controller.x_button_touched() {
+            if controller.primary_touched {
                 a_color = touch_color;
             }
-            if controller.x_button() {
+            if controller.primary_button {
                 a_color = pressed_color;
             }
 
@@ -154,14 +140,14 @@ fn draw_hand_gizmo(
             let joystick_base_vec =
                 face_translation_vec3 + joystick_offset_quat.mul_vec3(Vec3::new(-0.02, 0.0, 0.0));
             let mut joystick_color = off_color;
-            if controller.thumbstick_touch(Hand::Left) {
+            if controller.thumbstick_touched {
                 joystick_color = touch_color;
             }
 
             //base
             gizmos.circle(joystick_base_vec, face_quat_normal, 0.014, joystick_color);
 
-            let stick = controller.thumbstick(Hand::Left);
+            let stick = controller.thumbstick;
             let input = Vec3::new(stick.x, -stick.y, 0.0);
             let joystick_top_vec = face_translation_vec3
                 + joystick_offset_quat.mul_vec3(Vec3::new(-0.02, 0.0, -0.01))
@@ -170,10 +156,10 @@ fn draw_hand_gizmo(
             gizmos.circle(joystick_top_vec, face_quat_normal, 0.005, joystick_color);
 
             //trigger
-            let trigger_state = controller.trigger(Hand::Left);
+            let trigger_state = controller.trigger;
             let trigger_rotation = Quat::from_rotation_x(-0.75 * trigger_state);
             let mut trigger_color = off_color;
-            if controller.trigger_touched(Hand::Left) {
+            if controller.trigger_touched {
                 trigger_color = touch_color;
             }
             let trigger_transform = Transform {
@@ -206,7 +192,7 @@ fn draw_hand_gizmo(
             let face_quat = controller_quat.mul_quat(face_quat_offset);
             let face_quat_normal = face_quat.mul_vec3(Vec3::Z);
 
-            let _squeeze = controller.squeeze(Hand::Right);
+            let _squeeze = controller.squeeze;
             //info!("{:?}", squeeze);
             //grip
             gizmos.rect(
@@ -232,10 +218,10 @@ fn draw_hand_gizmo(
 
             //button b
             let mut b_color = off_color;
-            if controller.b_button_touched() {
+            if controller.secondary_touched {
                 b_color = touch_color;
             }
-            if controller.b_button() {
+            if controller.secondary_button {
                 b_color = pressed_color;
             }
 
@@ -246,10 +232,10 @@ fn draw_hand_gizmo(
 
             //button a
             let mut a_color = off_color;
-            if controller.a_button_touched() {
+            if controller.primary_touched {
                 a_color = touch_color;
             }
-            if controller.a_button() {
+            if controller.primary_button {
                 a_color = pressed_color;
             }
 
@@ -263,14 +249,14 @@ fn draw_hand_gizmo(
             let joystick_base_vec =
                 face_translation_vec3 + joystick_offset_quat.mul_vec3(Vec3::new(0.02, 0.0, 0.0));
             let mut joystick_color = off_color;
-            if controller.thumbstick_touch(Hand::Right) {
+            if controller.thumbstick_touched {
                 joystick_color = touch_color;
             }
 
             //base
             gizmos.circle(joystick_base_vec, face_quat_normal, 0.014, joystick_color);
 
-            let stick = controller.thumbstick(Hand::Right);
+            let stick = controller.thumbstick;
             let input = Vec3::new(stick.x, -stick.y, 0.0);
             let joystick_top_vec = face_translation_vec3
                 + joystick_offset_quat.mul_vec3(Vec3::new(0.02, 0.0, -0.01))
@@ -279,10 +265,10 @@ fn draw_hand_gizmo(
             gizmos.circle(joystick_top_vec, face_quat_normal, 0.005, joystick_color);
 
             //trigger
-            let trigger_state = controller.trigger(Hand::Right);
+            let trigger_state = controller.trigger;
             let trigger_rotation = Quat::from_rotation_x(-0.75 * trigger_state);
             let mut trigger_color = off_color;
-            if controller.trigger_touched(Hand::Right) {
+            if controller.trigger_touched {
                 trigger_color = touch_color;
             }
             let trigger_transform = Transform {
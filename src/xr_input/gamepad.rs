@@ -0,0 +1,276 @@
+use bevy::input::gamepad::{
+    GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent,
+    GamepadEvent, GamepadInfo,
+};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use super::{
+    buttons::{update_xr_buttons, XrAxis, XrAxisType, XrButton, XrButtonType},
+    Hand,
+};
+
+/// Registers each controller as a bevy [`Gamepad`] so gamepad driven code keeps working in the
+/// headset.
+///
+/// With [`XrGamepadLayout::PerHand`] every controller is a whole gamepad: the thumbstick is the
+/// left stick, the trigger is `RightTrigger2`, the grip is `LeftTrigger2`, A/X are `South`, B/Y
+/// are `East` and the menu button is `Start`.
+///
+/// With [`XrGamepadLayout::Combined`] the controllers are the two halves of a single gamepad: the
+/// left controller uses the left stick, `LeftTrigger2` for the trigger and `LeftTrigger` for the
+/// grip, the right controller the right ones. X is `West`, Y is `North`, A is `South`, B is `East`
+/// and the menu button is `Start`.
+///
+/// Input reaches bevy's gamepad resources one frame after it was synced.
+#[derive(Default)]
+pub struct XrGamepadPlugin {
+    pub settings: XrGamepadSettings,
+}
+
+impl Plugin for XrGamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone())
+            .init_resource::<XrGamepadValues>()
+            .add_systems(Startup, connect_xr_gamepads)
+            .add_systems(PreUpdate, send_xr_gamepad_events.after(update_xr_buttons));
+    }
+}
+
+/// how the controllers are mapped onto gamepads
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XrGamepadLayout {
+    /// a gamepad for each controller
+    #[default]
+    PerHand,
+    /// a single gamepad for both controllers, it uses the `left` gamepad id
+    Combined,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct XrGamepadSettings {
+    pub layout: XrGamepadLayout,
+    pub left: Gamepad,
+    pub right: Gamepad,
+}
+
+impl Default for XrGamepadSettings {
+    fn default() -> Self {
+        //far away from the ids gilrs hands out
+        Self {
+            layout: XrGamepadLayout::PerHand,
+            left: Gamepad::new(1000),
+            right: Gamepad::new(1001),
+        }
+    }
+}
+
+impl XrGamepadSettings {
+    pub fn gamepad(&self, hand: Hand) -> Gamepad {
+        match (self.layout, hand) {
+            (XrGamepadLayout::Combined, _) | (XrGamepadLayout::PerHand, Hand::Left) => self.left,
+            (XrGamepadLayout::PerHand, Hand::Right) => self.right,
+        }
+    }
+}
+
+/// the values last sent for every gamepad button and axis, events are only sent on change
+#[derive(Resource, Default)]
+pub struct XrGamepadValues {
+    pub buttons: HashMap<GamepadButton, f32>,
+    pub axes: HashMap<GamepadAxis, f32>,
+}
+
+pub fn gamepad_button_type(
+    layout: XrGamepadLayout,
+    hand: Hand,
+    button_type: XrButtonType,
+) -> Option<GamepadButtonType> {
+    if layout == XrGamepadLayout::PerHand {
+        return match button_type {
+            XrButtonType::Trigger => Some(GamepadButtonType::RightTrigger2),
+            XrButtonType::Squeeze => Some(GamepadButtonType::LeftTrigger2),
+            XrButtonType::Thumbstick => Some(GamepadButtonType::LeftThumb),
+            XrButtonType::Primary => Some(GamepadButtonType::South),
+            XrButtonType::Secondary => Some(GamepadButtonType::East),
+            XrButtonType::Menu => Some(GamepadButtonType::Start),
+            _ => None,
+        };
+    }
+    match (hand, button_type) {
+        (Hand::Left, XrButtonType::Trigger) => Some(GamepadButtonType::LeftTrigger2),
+        (Hand::Right, XrButtonType::Trigger) => Some(GamepadButtonType::RightTrigger2),
+        (Hand::Left, XrButtonType::Squeeze) => Some(GamepadButtonType::LeftTrigger),
+        (Hand::Right, XrButtonType::Squeeze) => Some(GamepadButtonType::RightTrigger),
+        (Hand::Left, XrButtonType::Thumbstick) => Some(GamepadButtonType::LeftThumb),
+        (Hand::Right, XrButtonType::Thumbstick) => Some(GamepadButtonType::RightThumb),
+        (Hand::Left, XrButtonType::Primary) => Some(GamepadButtonType::West),
+        (Hand::Right, XrButtonType::Primary) => Some(GamepadButtonType::South),
+        (Hand::Left, XrButtonType::Secondary) => Some(GamepadButtonType::North),
+        (Hand::Right, XrButtonType::Secondary) => Some(GamepadButtonType::East),
+        (Hand::Left, XrButtonType::Menu) => Some(GamepadButtonType::Start),
+        _ => None,
+    }
+}
+
+pub fn gamepad_axis_type(
+    layout: XrGamepadLayout,
+    hand: Hand,
+    axis_type: XrAxisType,
+) -> Option<GamepadAxisType> {
+    if layout == XrGamepadLayout::PerHand {
+        return match axis_type {
+            XrAxisType::ThumbstickX => Some(GamepadAxisType::LeftStickX),
+            XrAxisType::ThumbstickY => Some(GamepadAxisType::LeftStickY),
+            XrAxisType::Trigger => Some(GamepadAxisType::RightZ),
+            XrAxisType::Squeeze => Some(GamepadAxisType::LeftZ),
+        };
+    }
+    match (hand, axis_type) {
+        (Hand::Left, XrAxisType::ThumbstickX) => Some(GamepadAxisType::LeftStickX),
+        (Hand::Left, XrAxisType::ThumbstickY) => Some(GamepadAxisType::LeftStickY),
+        (Hand::Right, XrAxisType::ThumbstickX) => Some(GamepadAxisType::RightStickX),
+        (Hand::Right, XrAxisType::ThumbstickY) => Some(GamepadAxisType::RightStickY),
+        (Hand::Left, XrAxisType::Trigger) => Some(GamepadAxisType::LeftZ),
+        (Hand::Right, XrAxisType::Trigger) => Some(GamepadAxisType::RightZ),
+        _ => None,
+    }
+}
+
+pub fn connect_xr_gamepads(
+    settings: Res<XrGamepadSettings>,
+    mut gamepad_events: EventWriter<GamepadEvent>,
+) {
+    let gamepads = match settings.layout {
+        XrGamepadLayout::PerHand => vec![
+            (settings.left, "XR Left Controller"),
+            (settings.right, "XR Right Controller"),
+        ],
+        XrGamepadLayout::Combined => vec![(settings.left, "XR Controllers")],
+    };
+    for (gamepad, name) in gamepads {
+        let info = GamepadInfo { name: name.into() };
+        gamepad_events
+            .send(GamepadConnectionEvent::new(gamepad, GamepadConnection::Connected(info)).into());
+    }
+}
+
+pub fn send_xr_gamepad_events(
+    settings: Res<XrGamepadSettings>,
+    mut values: ResMut<XrGamepadValues>,
+    buttons: Res<Input<XrButton>>,
+    axes: Res<Axis<XrAxis>>,
+    mut gamepad_events: EventWriter<GamepadEvent>,
+) {
+    for hand in [Hand::Left, Hand::Right] {
+        let gamepad = settings.gamepad(hand);
+        for button_type in XrButtonType::ALL {
+            let gamepad_button_type = match gamepad_button_type(settings.layout, hand, button_type)
+            {
+                Some(gamepad_button_type) => gamepad_button_type,
+                None => continue,
+            };
+            //analog buttons keep their value so bevy's own thresholds apply
+            let value = match button_type {
+                XrButtonType::Trigger => axes.get(XrAxis::new(hand, XrAxisType::Trigger)),
+                XrButtonType::Squeeze => axes.get(XrAxis::new(hand, XrAxisType::Squeeze)),
+                _ => None,
+            };
+            let value = value.unwrap_or(if buttons.pressed(XrButton::new(hand, button_type)) {
+                1.0
+            } else {
+                0.0
+            });
+            let button = GamepadButton::new(gamepad, gamepad_button_type);
+            if values.buttons.insert(button, value) != Some(value) {
+                gamepad_events.send(
+                    GamepadButtonChangedEvent::new(gamepad, gamepad_button_type, value).into(),
+                );
+            }
+        }
+        for axis_type in [
+            XrAxisType::Trigger,
+            XrAxisType::Squeeze,
+            XrAxisType::ThumbstickX,
+            XrAxisType::ThumbstickY,
+        ] {
+            let gamepad_axis_type = match gamepad_axis_type(settings.layout, hand, axis_type) {
+                Some(gamepad_axis_type) => gamepad_axis_type,
+                None => continue,
+            };
+            let value = axes.get(XrAxis::new(hand, axis_type)).unwrap_or(0.0);
+            let axis = GamepadAxis::new(gamepad, gamepad_axis_type);
+            if values.axes.insert(axis, value) != Some(value) {
+                gamepad_events
+                    .send(GamepadAxisChangedEvent::new(gamepad, gamepad_axis_type, value).into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashSet;
+
+    use super::*;
+
+    const AXIS_TYPES: [XrAxisType; 4] = [
+        XrAxisType::Trigger,
+        XrAxisType::Squeeze,
+        XrAxisType::ThumbstickX,
+        XrAxisType::ThumbstickY,
+    ];
+
+    #[test]
+    fn no_gamepad_input_is_mapped_twice() {
+        for layout in [XrGamepadLayout::PerHand, XrGamepadLayout::Combined] {
+            let settings = XrGamepadSettings {
+                layout,
+                ..Default::default()
+            };
+            let mut buttons = HashSet::new();
+            let mut axes = HashSet::new();
+            for hand in [Hand::Left, Hand::Right] {
+                let gamepad = settings.gamepad(hand);
+                for button_type in XrButtonType::ALL {
+                    if let Some(button) = gamepad_button_type(layout, hand, button_type) {
+                        let button = GamepadButton::new(gamepad, button);
+                        assert!(buttons.insert(button), "{:?} is mapped twice", button);
+                    }
+                }
+                for axis_type in AXIS_TYPES {
+                    if let Some(axis) = gamepad_axis_type(layout, hand, axis_type) {
+                        let axis = GamepadAxis::new(gamepad, axis);
+                        assert!(axes.insert(axis), "{:?} is mapped twice", axis);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_hand_is_a_whole_gamepad() {
+        let settings = XrGamepadSettings::default();
+        assert_ne!(settings.gamepad(Hand::Left), settings.gamepad(Hand::Right));
+        for hand in [Hand::Left, Hand::Right] {
+            for button_type in [
+                XrButtonType::Trigger,
+                XrButtonType::Squeeze,
+                XrButtonType::Primary,
+                XrButtonType::Secondary,
+                XrButtonType::Menu,
+                XrButtonType::Thumbstick,
+            ] {
+                assert!(
+                    gamepad_button_type(settings.layout, hand, button_type).is_some(),
+                    "{:?} {:?} isn't mapped",
+                    hand,
+                    button_type
+                );
+            }
+            for axis_type in AXIS_TYPES {
+                assert!(gamepad_axis_type(settings.layout, hand, axis_type).is_some());
+            }
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use super::{
+    hand::{update_hand_skeletons, FingerCurl, HandBone, HandsResource},
+    Hand,
+};
+
+/// Recognizes hand gestures from the `HandBone` entities of `OpenXrHandInput`.
+///
+/// Only joint positions are used, so emulated and tracked hands are recognized the same way. The
+/// continuous values are in [`HandGestureStates`], discrete gestures are sent as
+/// [`HandGestureEvent`]s when they start and end.
+#[derive(Default)]
+pub struct XrHandGesturesPlugin;
+
+impl Plugin for XrHandGesturesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandGestureSettings>()
+            .init_resource::<HandPinchSettings>()
+            .init_resource::<HandGestureStates>()
+            .add_event::<HandGestureEvent>()
+            .add_systems(Update, update_hand_gestures.after(update_hand_skeletons));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HandGesture {
+    /// thumb and index tips together
+    Pinch,
+    /// all four fingers curled
+    Grab,
+    /// index extended, the other fingers curled
+    Point,
+    /// thumb extended and pointing up, the fingers curled
+    ThumbsUp,
+    /// every finger extended
+    OpenPalm,
+}
+
+impl HandGesture {
+    pub const ALL: [HandGesture; 5] = [
+        HandGesture::Pinch,
+        HandGesture::Grab,
+        HandGesture::Point,
+        HandGesture::ThumbsUp,
+        HandGesture::OpenPalm,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandGesturePhase {
+    Started,
+    Ended,
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandGestureEvent {
+    pub hand: Hand,
+    pub gesture: HandGesture,
+    pub phase: HandGesturePhase,
+}
+
+/// a value has to reach `start` to become active and stays active until it drops below `end`
+#[derive(Clone, Copy, Debug)]
+pub struct GestureThreshold {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl GestureThreshold {
+    pub fn new(start: f32, end: f32) -> Self {
+        Self { start, end }
+    }
+
+    pub fn is_active(&self, value: f32, was_active: bool) -> bool {
+        if was_active {
+            value >= self.end
+        } else {
+            value >= self.start
+        }
+    }
+}
+
+/// how strongly the thumb pinches a finger, from 0.0 at `open_distance` apart to 1.0 at
+/// `closed_distance`
+pub fn pinch_strength(
+    thumb_tip: Vec3,
+    finger_tip: Vec3,
+    closed_distance: f32,
+    open_distance: f32,
+) -> f32 {
+    let distance = thumb_tip.distance(finger_tip);
+    (1.0 - (distance - closed_distance) / (open_distance - closed_distance)).clamp(0.0, 1.0)
+}
+
+/// when the thumb pinches a finger, shared by the pinch gesture and the estimated hand aim
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HandPinchSettings {
+    /// thumb to fingertip distance in meters at which the pinch strength is 1.0
+    pub closed_distance: f32,
+    /// thumb to fingertip distance in meters at which the pinch strength is 0.0
+    pub open_distance: f32,
+    /// the pinch strength at which the thumb and a finger count as pinching
+    pub threshold: GestureThreshold,
+}
+
+impl Default for HandPinchSettings {
+    fn default() -> Self {
+        Self {
+            closed_distance: 0.015,
+            open_distance: 0.08,
+            threshold: GestureThreshold::new(0.9, 0.7),
+        }
+    }
+}
+
+impl HandPinchSettings {
+    pub fn strength(&self, thumb_tip: Vec3, finger_tip: Vec3) -> f32 {
+        pinch_strength(
+            thumb_tip,
+            finger_tip,
+            self.closed_distance,
+            self.open_distance,
+        )
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HandGestureSettings {
+    pub grab: GestureThreshold,
+    /// how curled a finger has to be to count as curled
+    pub curled: GestureThreshold,
+    /// how straight, one minus the curl, a finger has to be to count as extended
+    pub extended: GestureThreshold,
+    /// how close to straight up the thumb has to point for a thumbs up, as the cosine of the angle
+    pub thumb_up: GestureThreshold,
+}
+
+impl Default for HandGestureSettings {
+    fn default() -> Self {
+        Self {
+            grab: GestureThreshold::new(0.8, 0.6),
+            curled: GestureThreshold::new(0.7, 0.5),
+            extended: GestureThreshold::new(0.75, 0.6),
+            thumb_up: GestureThreshold::new(0.7, 0.5),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HandGestureState {
+    /// 0.0 with the thumb and index tips apart to 1.0 when they touch
+    pub pinch_strength: f32,
+    /// the average curl of the four fingers
+    pub grab_strength: f32,
+    pub curl: FingerCurl,
+    /// how straight up the thumb points, 1.0 is straight up
+    pub thumb_up: f32,
+    pub active: HashSet<HandGesture>,
+}
+
+impl HandGestureState {
+    pub fn is_active(&self, gesture: HandGesture) -> bool {
+        self.active.contains(&gesture)
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct HandGestureStates {
+    pub left: HandGestureState,
+    pub right: HandGestureState,
+}
+
+impl HandGestureStates {
+    pub fn get(&self, hand: Hand) -> &HandGestureState {
+        match hand {
+            Hand::Left => &self.left,
+            Hand::Right => &self.right,
+        }
+    }
+
+    pub fn get_mut(&mut self, hand: Hand) -> &mut HandGestureState {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
+    }
+}
+
+/// the summed bend at each joint of a chain, from its first to its last joint
+fn chain_bend(joints: &[Vec3]) -> f32 {
+    joints
+        .windows(3)
+        .map(|joint| {
+            let a = joint[1] - joint[0];
+            let b = joint[2] - joint[1];
+            if a.length_squared() > 0.0 && b.length_squared() > 0.0 {
+                a.angle_between(b)
+            } else {
+                0.0
+            }
+        })
+        .sum()
+}
+
+/// curl from the joint positions, so it works for emulated hands where only the positions move
+fn finger_curl(joints: &[Vec3], max_bend: f32) -> f32 {
+    (chain_bend(joints) / max_bend).clamp(0.0, 1.0)
+}
+
+fn gesture_active(
+    gesture: HandGesture,
+    state: &HandGestureState,
+    settings: &HandGestureSettings,
+    pinch: &HandPinchSettings,
+) -> bool {
+    let was_active = state.is_active(gesture);
+    let curled = |curl: f32| settings.curled.is_active(curl, was_active);
+    let extended = |curl: f32| settings.extended.is_active(1.0 - curl, was_active);
+    let curl = state.curl;
+    match gesture {
+        HandGesture::Pinch => pinch.threshold.is_active(state.pinch_strength, was_active),
+        HandGesture::Grab => settings.grab.is_active(state.grab_strength, was_active),
+        HandGesture::Point => {
+            extended(curl.index) && curled(curl.middle) && curled(curl.ring) && curled(curl.little)
+        }
+        HandGesture::ThumbsUp => {
+            extended(curl.thumb)
+                && settings.thumb_up.is_active(state.thumb_up, was_active)
+                && curl.fingers().into_iter().all(curled)
+        }
+        HandGesture::OpenPalm => extended(curl.thumb) && curl.fingers().into_iter().all(extended),
+    }
+}
+
+pub fn update_hand_gestures(
+    settings: Res<HandGestureSettings>,
+    pinch: Res<HandPinchSettings>,
+    mut states: ResMut<HandGestureStates>,
+    mut events: EventWriter<HandGestureEvent>,
+    hands: Option<Res<HandsResource>>,
+    hand_bone_query: Query<&Transform, With<HandBone>>,
+) {
+    let hands = match hands {
+        Some(hands) => hands,
+        None => return,
+    };
+    for hand in [Hand::Left, Hand::Right] {
+        let hand_resource = hands.get(hand);
+        let joint = |bone: HandBone| {
+            hand_bone_query
+                .get(hand_resource.entity(bone))
+                .map(|transform| transform.translation)
+                .unwrap_or_default()
+        };
+        //bends at the proximal, intermediate and distal joints, a fist bends about 250 degrees
+        let finger = |bones: [HandBone; 5]| finger_curl(&bones.map(joint), PI * 1.4);
+        let curl = FingerCurl {
+            thumb: finger_curl(
+                &[
+                    joint(HandBone::ThumbMetacarpal),
+                    joint(HandBone::ThumbProximal),
+                    joint(HandBone::ThumbDistal),
+                    joint(HandBone::ThumbTip),
+                ],
+                PI * 0.6,
+            ),
+            index: finger([
+                HandBone::IndexMetacarpal,
+                HandBone::IndexProximal,
+                HandBone::IndexIntermediate,
+                HandBone::IndexDistal,
+                HandBone::IndexTip,
+            ]),
+            middle: finger([
+                HandBone::MiddleMetacarpal,
+                HandBone::MiddleProximal,
+                HandBone::MiddleIntermediate,
+                HandBone::MiddleDistal,
+                HandBone::MiddleTip,
+            ]),
+            ring: finger([
+                HandBone::RingMetacarpal,
+                HandBone::RingProximal,
+                HandBone::RingIntermediate,
+                HandBone::RingDistal,
+                HandBone::RingTip,
+            ]),
+            little: finger([
+                HandBone::LittleMetacarpal,
+                HandBone::LittleProximal,
+                HandBone::LittleIntermediate,
+                HandBone::LittleDistal,
+                HandBone::LittleTip,
+            ]),
+        };
+        let thumb_direction =
+            (joint(HandBone::ThumbTip) - joint(HandBone::ThumbProximal)).normalize_or_zero();
+
+        let state = states.get_mut(hand);
+        state.curl = curl;
+        state.pinch_strength = pinch.strength(joint(HandBone::ThumbTip), joint(HandBone::IndexTip));
+        state.grab_strength = curl.fingers().iter().sum::<f32>() / 4.0;
+        state.thumb_up = thumb_direction.dot(Vec3::Y);
+        for gesture in HandGesture::ALL {
+            let active = gesture_active(gesture, state, &settings, &pinch);
+            let phase = match (state.is_active(gesture), active) {
+                (false, true) => HandGesturePhase::Started,
+                (true, false) => HandGesturePhase::Ended,
+                _ => continue,
+            };
+            if active {
+                state.active.insert(gesture);
+            } else {
+                state.active.remove(&gesture);
+            }
+            events.send(HandGestureEvent {
+                hand,
+                gesture,
+                phase,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_has_hysteresis() {
+        let threshold = GestureThreshold::new(0.8, 0.6);
+        //rising, inactive until the start value
+        assert!(!threshold.is_active(0.7, false));
+        assert!(threshold.is_active(0.8, false));
+        //falling, active until below the end value
+        assert!(threshold.is_active(0.7, true));
+        assert!(threshold.is_active(0.6, true));
+        assert!(!threshold.is_active(0.59, true));
+    }
+
+    #[test]
+    fn pinch_strength_ramps_between_the_distances() {
+        let pinch = HandPinchSettings::default();
+        let tip = |distance: f32| Vec3::X * distance;
+        assert_eq!(pinch.strength(Vec3::ZERO, tip(0.2)), 0.0);
+        assert_eq!(pinch.strength(Vec3::ZERO, tip(pinch.open_distance)), 0.0);
+        assert_eq!(pinch.strength(Vec3::ZERO, tip(pinch.closed_distance)), 1.0);
+        assert_eq!(pinch.strength(Vec3::ZERO, Vec3::ZERO), 1.0);
+        let halfway = (pinch.closed_distance + pinch.open_distance) / 2.0;
+        assert!((pinch.strength(Vec3::ZERO, tip(halfway)) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn straight_chain_has_no_curl() {
+        let straight = [Vec3::ZERO, Vec3::Y, Vec3::Y * 2.0, Vec3::Y * 3.0];
+        assert_eq!(finger_curl(&straight, PI), 0.0);
+        //two right angles, clamped to a full curl
+        let bent = [Vec3::ZERO, Vec3::Y, Vec3::Y + Vec3::Z, Vec3::Z];
+        assert!((finger_curl(&bent, PI) - 1.0).abs() < 1e-6);
+        assert!((finger_curl(&bent, PI * 2.0) - 0.5).abs() < 1e-6);
+    }
+}
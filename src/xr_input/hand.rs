@@ -1,22 +1,28 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::{
-    default, info, Color, Commands, Component, Entity, Gizmos, GlobalTransform, Plugin, PostUpdate,
-    PreUpdate, Quat, Query, Res, ResMut, Resource, SpatialBundle, Startup, Transform, Update, Vec3,
-    With,
+    default, info, Assets, Color, Commands, Component, Entity, Event, EventWriter, Gizmos,
+    GlobalTransform, Has, IntoSystemConfigs, Plugin, PostUpdate, PreUpdate, Quat, Query, Res,
+    ResMut, Resource, SpatialBundle, Startup, Time, Transform, Update, Vec3, With, Without,
 };
 use openxr::{HandJoint, Posef};
 
-use crate::{
-    input::XrInput,
-    resources::{XrFrameState, XrInstance, XrSession},
-    xr_input::Vec3Conv,
-};
+use crate::xr_input::Vec3Conv;
 
 use super::{
-    hand_poses::get_simulated_open_hand_transforms,
-    oculus_touch::OculusController,
-    trackers::{OpenXRLeftController, OpenXRRightController, OpenXRTracker},
+    controllers::{XrControllerInput, XrControllerInputSet},
+    gestures::HandPinchSettings,
+    hand_poses::{get_simulated_open_hand_transforms, HandPose, HandPoseBlend},
+    hand_tracking::{
+        locate_tracked_hands, setup_hand_trackers, update_hand_aim, update_hand_bones_openxr,
+        HandAimSettings, HandAimStates, HandDataSource, HandDataSources, HandTrackingSettings,
+        TrackedHandJoints,
+    },
+    trackers::{
+        update_open_xr_controllers, update_tracking_states, AimPose, OpenXRController,
+        OpenXRLeftController, OpenXRRightController, OpenXRTracker, PalmPose, XrTrackingStates,
+        XrVelocity,
+    },
     Hand,
 };
 
@@ -26,11 +32,28 @@ pub struct OpenXrHandInput;
 
 impl Plugin for OpenXrHandInput {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Update, update_hand_skeletons)
-            .add_systems(PreUpdate, update_hand_states)
+        app.add_systems(Update, update_hand_skeletons.after(update_tracking_states))
+            .add_systems(PreUpdate, update_hand_states.after(XrControllerInputSet))
             .add_systems(Startup, spawn_hand_entities)
+            .add_systems(Startup, setup_hand_trackers)
+            .init_resource::<HandTrackingSettings>()
+            .init_resource::<TrackedHandJoints>()
+            .init_resource::<HandDataSources>()
+            .add_systems(Update, locate_tracked_hands.before(update_hand_skeletons))
             .insert_resource(HandStatesResource::default())
-            .insert_resource(HandInputSource::default());
+            .init_resource::<HandCurlSettings>()
+            .insert_resource(HandInputSource::default())
+            .init_resource::<ActiveHandInputSources>()
+            .add_event::<HandInputSourceChanged>()
+            .init_resource::<HandAimSettings>()
+            .init_resource::<HandPinchSettings>()
+            .init_resource::<HandAimStates>()
+            .add_systems(
+                Update,
+                update_hand_aim
+                    .after(update_hand_skeletons)
+                    .after(update_open_xr_controllers),
+            );
     }
 }
 
@@ -44,24 +67,77 @@ impl Plugin for HandInputDebugRenderer{
     }
 }
 
-#[derive(Resource)]
+/// where the hand skeletons come from, `Automatic` picks per hand every frame
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HandInputSource {
+    /// posed from the controller input
     Emulated,
+    /// tracked joints from `XR_EXT_hand_tracking`
     OpenXr,
+    /// tracked joints while the runtime tracks the hand, emulated while the controller is tracked
+    Automatic,
 }
 
 impl Default for HandInputSource {
     fn default() -> Self {
-        HandInputSource::Emulated
+        HandInputSource::Automatic
+    }
+}
+
+/// the source each hand is currently posed from, never `Automatic`
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ActiveHandInputSources {
+    pub left: HandInputSource,
+    pub right: HandInputSource,
+}
+
+impl Default for ActiveHandInputSources {
+    fn default() -> Self {
+        Self {
+            left: HandInputSource::Emulated,
+            right: HandInputSource::Emulated,
+        }
+    }
+}
+
+impl ActiveHandInputSources {
+    pub fn get(&self, hand: Hand) -> HandInputSource {
+        match hand {
+            Hand::Left => self.left,
+            Hand::Right => self.right,
+        }
+    }
+
+    pub fn get_mut(&mut self, hand: Hand) -> &mut HandInputSource {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
     }
 }
 
+/// sent when a hand switches between emulated and tracked joints
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandInputSourceChanged {
+    pub hand: Hand,
+    pub source: HandInputSource,
+}
+
 #[derive(Resource, Default)]
 pub struct HandsResource {
     pub left: HandResource,
     pub right: HandResource,
 }
 
+impl HandsResource {
+    pub fn get(&self, hand: Hand) -> &HandResource {
+        match hand {
+            Hand::Left => &self.left,
+            Hand::Right => &self.right,
+        }
+    }
+}
+
 pub struct HandResource {
     pub palm: Entity,
     pub wrist: Entity,
@@ -72,6 +148,39 @@ pub struct HandResource {
     pub little: LittleResource,
 }
 
+impl HandResource {
+    pub fn entity(&self, bone: HandBone) -> Entity {
+        match bone {
+            HandBone::Palm => self.palm,
+            HandBone::Wrist => self.wrist,
+            HandBone::ThumbMetacarpal => self.thumb.metacarpal,
+            HandBone::ThumbProximal => self.thumb.proximal,
+            HandBone::ThumbDistal => self.thumb.distal,
+            HandBone::ThumbTip => self.thumb.tip,
+            HandBone::IndexMetacarpal => self.index.metacarpal,
+            HandBone::IndexProximal => self.index.proximal,
+            HandBone::IndexIntermediate => self.index.intermediate,
+            HandBone::IndexDistal => self.index.distal,
+            HandBone::IndexTip => self.index.tip,
+            HandBone::MiddleMetacarpal => self.middle.metacarpal,
+            HandBone::MiddleProximal => self.middle.proximal,
+            HandBone::MiddleIntermediate => self.middle.intermediate,
+            HandBone::MiddleDistal => self.middle.distal,
+            HandBone::MiddleTip => self.middle.tip,
+            HandBone::RingMetacarpal => self.ring.metacarpal,
+            HandBone::RingProximal => self.ring.proximal,
+            HandBone::RingIntermediate => self.ring.intermediate,
+            HandBone::RingDistal => self.ring.distal,
+            HandBone::RingTip => self.ring.tip,
+            HandBone::LittleMetacarpal => self.little.metacarpal,
+            HandBone::LittleProximal => self.little.proximal,
+            HandBone::LittleIntermediate => self.little.intermediate,
+            HandBone::LittleDistal => self.little.distal,
+            HandBone::LittleTip => self.little.tip,
+        }
+    }
+}
+
 impl Default for HandResource {
     fn default() -> Self {
         Self {
@@ -179,34 +288,7 @@ impl Default for LittleResource {
 
 pub fn spawn_hand_entities(mut commands: Commands) {
     let hands = [Hand::Left, Hand::Right];
-    let bones = [
-        HandBone::Palm,
-        HandBone::Wrist,
-        HandBone::ThumbMetacarpal,
-        HandBone::ThumbProximal,
-        HandBone::ThumbDistal,
-        HandBone::ThumbTip,
-        HandBone::IndexMetacarpal,
-        HandBone::IndexProximal,
-        HandBone::IndexIntermediate,
-        HandBone::IndexDistal,
-        HandBone::IndexTip,
-        HandBone::MiddleMetacarpal,
-        HandBone::MiddleProximal,
-        HandBone::MiddleIntermediate,
-        HandBone::MiddleDistal,
-        HandBone::MiddleTip,
-        HandBone::RingMetacarpal,
-        HandBone::RingProximal,
-        HandBone::RingIntermediate,
-        HandBone::RingDistal,
-        HandBone::RingTip,
-        HandBone::LittleMetacarpal,
-        HandBone::LittleProximal,
-        HandBone::LittleIntermediate,
-        HandBone::LittleDistal,
-        HandBone::LittleTip,
-    ];
+    let bones = HandBone::ALL;
     //hand resource
     let mut hand_resource = HandsResource { ..default() };
     for hand in hands.iter() {
@@ -215,10 +297,19 @@ pub fn spawn_hand_entities(mut commands: Commands) {
                 .spawn((
                     SpatialBundle::default(),
                     bone.clone(),
+                    HandBoneRadius(get_bone_gizmo_style(bone).0),
+                    //only tracked joints have velocities, emulated hands move with the controller
+                    XrVelocity::default(),
                     OpenXRTracker,
                     hand.clone(),
                 ))
                 .id();
+            //the palm carries the aim of the hand, see `update_hand_aim`
+            if *bone == HandBone::Palm {
+                commands
+                    .entity(boneid)
+                    .insert(AimPose(Transform::default()));
+            }
             match hand {
                 Hand::Left => match bone {
                     HandBone::Palm => hand_resource.left.palm = boneid,
@@ -286,7 +377,7 @@ pub fn spawn_hand_entities(mut commands: Commands) {
     commands.insert_resource(hand_resource);
 }
 
-#[derive(Component, Debug, Clone, Copy)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum HandBone {
     Palm,
     Wrist,
@@ -316,114 +407,95 @@ pub enum HandBone {
     LittleTip,
 }
 
+impl HandBone {
+    pub const ALL: [HandBone; 26] = [
+        HandBone::Palm,
+        HandBone::Wrist,
+        HandBone::ThumbMetacarpal,
+        HandBone::ThumbProximal,
+        HandBone::ThumbDistal,
+        HandBone::ThumbTip,
+        HandBone::IndexMetacarpal,
+        HandBone::IndexProximal,
+        HandBone::IndexIntermediate,
+        HandBone::IndexDistal,
+        HandBone::IndexTip,
+        HandBone::MiddleMetacarpal,
+        HandBone::MiddleProximal,
+        HandBone::MiddleIntermediate,
+        HandBone::MiddleDistal,
+        HandBone::MiddleTip,
+        HandBone::RingMetacarpal,
+        HandBone::RingProximal,
+        HandBone::RingIntermediate,
+        HandBone::RingDistal,
+        HandBone::RingTip,
+        HandBone::LittleMetacarpal,
+        HandBone::LittleProximal,
+        HandBone::LittleIntermediate,
+        HandBone::LittleDistal,
+        HandBone::LittleTip,
+    ];
+
+    /// the matching `XR_EXT_hand_tracking` joint
+    pub fn joint(&self) -> HandJoint {
+        match_index(self)
+    }
+
+    /// the joint this one hangs off, the palm is the root and the metacarpals hang off the wrist
+    pub fn parent(&self) -> Option<HandBone> {
+        match self {
+            HandBone::Palm => None,
+            HandBone::Wrist => Some(HandBone::Palm),
+            HandBone::ThumbMetacarpal
+            | HandBone::IndexMetacarpal
+            | HandBone::MiddleMetacarpal
+            | HandBone::RingMetacarpal
+            | HandBone::LittleMetacarpal => Some(HandBone::Wrist),
+            bone => Some(HandBone::ALL[*bone as usize - 1]),
+        }
+    }
+}
+
+/// the radius of a hand joint in meters, reported by the runtime when hands are tracked
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HandBoneRadius(pub f32);
+
 pub fn update_hand_states(
-    oculus_controller: Res<OculusController>,
     hand_states_option: Option<ResMut<HandStatesResource>>,
-    frame_state: Res<XrFrameState>,
-    xr_input: Res<XrInput>,
-    instance: Res<XrInstance>,
-    session: Res<XrSession>,
+    curl_settings: Res<HandCurlSettings>,
+    time: Res<Time>,
+    controller_query: Query<
+        (
+            &XrControllerInput,
+            Has<OpenXRLeftController>,
+            Has<OpenXRRightController>,
+        ),
+        With<OpenXRController>,
+    >,
 ) {
     match hand_states_option {
         Some(mut hands) => {
-            //lock frame
-            let frame_state = *frame_state.lock().unwrap();
-            //get controller
-            let controller =
-                oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
-
-            //right hand
-            let squeeze = controller.squeeze(Hand::Right);
-            let trigger_state = controller.trigger(Hand::Right);
-            let calc_trigger_state = match controller.trigger_touched(Hand::Right) {
-                true => match trigger_state > 0.0 {
-                    true => TriggerState::PULLED,
-                    false => TriggerState::TOUCHED,
-                },
-                false => TriggerState::OFF,
-            };
-            //button a
-            let mut a_state = ButtonState::OFF;
-            if controller.a_button_touched() {
-                a_state = ButtonState::TOUCHED;
-            }
-            if controller.a_button() {
-                a_state = ButtonState::PRESSED;
-            }
-
-            //button b
-            let mut b_state = ButtonState::OFF;
-            if controller.b_button_touched() {
-                b_state = ButtonState::TOUCHED;
-            }
-            if controller.b_button() {
-                b_state = ButtonState::PRESSED;
-            }
-
-            let thumbstick_state = controller.thumbstick(Hand::Right);
-            let calc_thumbstick_state = match controller.thumbstick_touch(Hand::Right) {
-                true => match thumbstick_state.x > 0.0 || thumbstick_state.y > 0.0 {
-                    true => ThumbstickState::PRESSED,
-                    false => ThumbstickState::TOUCHED,
-                },
-                false => ThumbstickState::OFF,
-            };
-
-            let right_state = HandState {
-                grip: squeeze,
-                trigger_state: calc_trigger_state,
-                a_button: a_state,
-                b_button: b_state,
-                thumbstick: calc_thumbstick_state,
-            };
-
-            //left
-            let squeeze = controller.squeeze(Hand::Left);
-            let trigger_state = controller.trigger(Hand::Left);
-            let calc_trigger_state = match controller.trigger_touched(Hand::Left) {
-                true => match trigger_state > 0.0 {
-                    true => TriggerState::PULLED,
-                    false => TriggerState::TOUCHED,
-                },
-                false => TriggerState::OFF,
-            };
-            //button a
-            let mut a_state = ButtonState::OFF;
-            if controller.x_button_touched() {
-                a_state = ButtonState::TOUCHED;
-            }
-            if controller.x_button() {
-                a_state = ButtonState::PRESSED;
-            }
-
-            //button b
-            let mut b_state = ButtonState::OFF;
-            if controller.y_button_touched() {
-                b_state = ButtonState::TOUCHED;
-            }
-            if controller.y_button() {
-                b_state = ButtonState::PRESSED;
+            for hand in [Hand::Left, Hand::Right] {
+                //every tracker of a hand has the same input, only smooth it once
+                let input = match controller_query.iter().find(|(_, left, right)| match hand {
+                    Hand::Left => *left,
+                    Hand::Right => *right,
+                }) {
+                    Some((input, ..)) => input,
+                    None => continue,
+                };
+                let hand_state = match hand {
+                    Hand::Left => &mut hands.left,
+                    Hand::Right => &mut hands.right,
+                };
+                let target = curl_settings.target_curl(input);
+                let curl = curl_settings.smooth(hand_state.curl, target, time.delta_seconds());
+                *hand_state = HandState {
+                    curl,
+                    ..input.hand_state()
+                };
             }
-
-            let thumbstick_state = controller.thumbstick(Hand::Left);
-            let calc_thumbstick_state = match controller.thumbstick_touch(Hand::Left) {
-                true => match thumbstick_state.x > 0.0 || thumbstick_state.y > 0.0 {
-                    true => ThumbstickState::PRESSED,
-                    false => ThumbstickState::TOUCHED,
-                },
-                false => ThumbstickState::OFF,
-            };
-
-            let left_state = HandState {
-                grip: squeeze,
-                trigger_state: calc_trigger_state,
-                a_button: a_state,
-                b_button: b_state,
-                thumbstick: calc_thumbstick_state,
-            };
-
-            hands.left = left_state;
-            hands.right = right_state;
         }
         None => info!("hand states resource not init yet"),
     }
@@ -479,37 +551,17 @@ pub struct HandState {
     pub a_button: ButtonState,
     pub b_button: ButtonState,
     pub thumbstick: ThumbstickState,
+    /// the smoothed curl the emulated fingers are posed with
+    pub curl: FingerCurl,
 }
 
 impl HandState {
     pub fn get_index_curl(&self) -> f32 {
-        match self.trigger_state {
-            TriggerState::OFF => 0.0,
-            TriggerState::TOUCHED => 0.50,
-            TriggerState::PULLED => 1.0,
-        }
+        self.curl.index
     }
 
     pub fn get_thumb_curl(&self) -> f32 {
-        match self.thumbstick {
-            ThumbstickState::OFF => (),
-            ThumbstickState::TOUCHED => return 0.25,
-            ThumbstickState::PRESSED => return 0.25,
-        };
-
-        match self.a_button {
-            ButtonState::OFF => (),
-            ButtonState::TOUCHED => return 0.25,
-            ButtonState::PRESSED => return 0.25,
-        };
-
-        match self.b_button {
-            ButtonState::OFF => (),
-            ButtonState::TOUCHED => return 0.25,
-            ButtonState::PRESSED => return 0.25,
-        };
-        //if no thumb actions taken return open position
-        return 0.0;
+        self.curl.thumb
     }
 }
 
@@ -521,6 +573,148 @@ impl Default for HandState {
             a_button: Default::default(),
             b_button: Default::default(),
             thumbstick: Default::default(),
+            curl: Default::default(),
+        }
+    }
+}
+
+/// how curled each finger is, 0.0 is straight and 1.0 fully curled
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FingerCurl {
+    pub thumb: f32,
+    pub index: f32,
+    pub middle: f32,
+    pub ring: f32,
+    pub little: f32,
+}
+
+impl FingerCurl {
+    /// the four fingers without the thumb
+    pub fn fingers(&self) -> [f32; 4] {
+        [self.index, self.middle, self.ring, self.little]
+    }
+
+    pub fn lerp(&self, other: FingerCurl, t: f32) -> FingerCurl {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        FingerCurl {
+            thumb: lerp(self.thumb, other.thumb),
+            index: lerp(self.index, other.index),
+            middle: lerp(self.middle, other.middle),
+            ring: lerp(self.ring, other.ring),
+            little: lerp(self.little, other.little),
+        }
+    }
+}
+
+/// how far the joints of an emulated finger bend in degrees, from the proximal joint out, the
+/// thumb has no intermediate joint so its last entry is the tip
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FingerCurve {
+    pub open: [f32; 3],
+    pub closed: [f32; 3],
+    /// shapes the curl before it is applied, above 1.0 the finger stays open longer
+    pub exponent: f32,
+}
+
+impl FingerCurve {
+    pub fn angle(&self, segment: usize, curl: f32) -> f32 {
+        let curl = curl.clamp(0.0, 1.0).powf(self.exponent);
+        self.open[segment] + (self.closed[segment] - self.open[segment]) * curl
+    }
+}
+
+impl Default for FingerCurve {
+    fn default() -> Self {
+        Self {
+            open: [5.0, 5.0, 5.0],
+            closed: [5.0, 85.0, 85.0],
+            exponent: 1.0,
+        }
+    }
+}
+
+/// how the controller input curls the fingers of emulated hands
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct HandCurlSettings {
+    /// seconds for the curl to get about two thirds of the way to the input, 0.0 follows it
+    /// instantly
+    pub smoothing: f32,
+    /// the index curl while the trigger is touched but not pulled
+    pub trigger_touched_curl: f32,
+    /// the thumb curl while it rests on the thumbrest, the stick or a button
+    pub thumb_touched_curl: f32,
+    /// the thumb curl while it presses a button or the stick
+    pub thumb_pressed_curl: f32,
+    /// the curl of an index or thumb that hovers near the controller, only reported with
+    /// `XR_FB_touch_controller_proximity`
+    pub proximity_curl: f32,
+    pub thumb: FingerCurve,
+    pub index: FingerCurve,
+    pub middle: FingerCurve,
+    pub ring: FingerCurve,
+    pub little: FingerCurve,
+}
+
+impl Default for HandCurlSettings {
+    fn default() -> Self {
+        Self {
+            smoothing: 0.05,
+            trigger_touched_curl: 0.4,
+            thumb_touched_curl: 0.25,
+            thumb_pressed_curl: 0.35,
+            proximity_curl: 0.15,
+            thumb: FingerCurve::default(),
+            index: FingerCurve::default(),
+            middle: FingerCurve::default(),
+            ring: FingerCurve::default(),
+            little: FingerCurve::default(),
+        }
+    }
+}
+
+impl HandCurlSettings {
+    /// the curl the fingers move towards, squeeze closes the middle, ring and little fingers
+    pub fn target_curl(&self, input: &XrControllerInput) -> FingerCurl {
+        let near_curl = |proximity: Option<bool>| match proximity {
+            Some(true) => self.proximity_curl,
+            _ => 0.0,
+        };
+        let index = if input.trigger_touched || input.trigger > 0.0 {
+            self.trigger_touched_curl + (1.0 - self.trigger_touched_curl) * input.trigger
+        } else {
+            near_curl(input.trigger_proximity)
+        };
+        let thumb_pressed =
+            input.primary_button || input.secondary_button || input.thumbstick_click;
+        let thumb_touched = input.primary_touched
+            || input.secondary_touched
+            || input.thumbstick_touched
+            || input.thumbrest_touched;
+        let thumb = match (thumb_pressed, thumb_touched) {
+            (true, _) => self.thumb_pressed_curl,
+            (false, true) => self.thumb_touched_curl,
+            (false, false) => near_curl(input.thumb_proximity),
+        };
+        FingerCurl {
+            thumb,
+            index,
+            middle: input.squeeze,
+            ring: input.squeeze,
+            little: input.squeeze,
+        }
+    }
+
+    /// moves `current` towards `target` over `delta_seconds`
+    pub fn smooth(
+        &self,
+        current: FingerCurl,
+        target: FingerCurl,
+        delta_seconds: f32,
+    ) -> FingerCurl {
+        if self.smoothing > 0.0 {
+            current.lerp(target, 1.0 - (-delta_seconds / self.smoothing).exp())
+        } else {
+            target
         }
     }
 }
@@ -529,19 +723,14 @@ pub fn update_hand_bones_emulated(
     controller_transform: Transform,
     hand: Hand,
     hand_state: HandState,
+    curl_settings: &HandCurlSettings,
     hand_bone_query: &mut Query<(&mut Transform, &HandBone, &Hand)>,
 ) {
-    let left_hand_rot = Quat::from_rotation_y(180.0 * PI / 180.0);
     let hand_translation: Vec3 = match hand {
         Hand::Left => controller_transform.translation,
         Hand::Right => controller_transform.translation,
     };
 
-    let controller_quat: Quat = match hand {
-        Hand::Left => controller_transform.rotation.mul_quat(left_hand_rot),
-        Hand::Right => controller_transform.rotation,
-    };
-
     let splay_direction = match hand {
         Hand::Left => -1.0,
         Hand::Right => 1.0,
@@ -550,13 +739,9 @@ pub fn update_hand_bones_emulated(
     let mut calc_transforms = [Transform::default(); 26];
 
     //curl represents how closed the hand is from 0 to 1;
-    let grip_curl = hand_state.grip;
-    let index_curl = hand_state.get_index_curl();
-    let thumb_curl = hand_state.get_thumb_curl();
+    let curl = hand_state.curl;
     //get palm quat
-    let y = Quat::from_rotation_y(-90.0 * PI / 180.0);
-    let x = Quat::from_rotation_x(-90.0 * PI / 180.0);
-    let palm_quat = controller_quat.mul_quat(y).mul_quat(x);
+    let palm_quat = emulated_palm_rotation(controller_transform.rotation, hand);
     //get simulated bones
     let hand_transform_array: [Transform; 26] = get_simulated_open_hand_transforms(hand);
     //palm
@@ -588,7 +773,7 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, thumb_curl);
+                let curl_angle: f32 = get_bone_curl_angle(*bone, curl.thumb, &curl_settings.thumb);
                 let tp_lrot = Quat::from_rotation_y(splay_direction * curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -637,7 +822,7 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, index_curl);
+                let curl_angle: f32 = get_bone_curl_angle(*bone, curl.index, &curl_settings.index);
                 let tp_lrot = Quat::from_rotation_x(curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -686,7 +871,8 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, grip_curl);
+                let curl_angle: f32 =
+                    get_bone_curl_angle(*bone, curl.middle, &curl_settings.middle);
                 let tp_lrot = Quat::from_rotation_x(curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -734,7 +920,7 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, grip_curl);
+                let curl_angle: f32 = get_bone_curl_angle(*bone, curl.ring, &curl_settings.ring);
                 let tp_lrot = Quat::from_rotation_x(curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -783,7 +969,8 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, grip_curl);
+                let curl_angle: f32 =
+                    get_bone_curl_angle(*bone, curl.little, &curl_settings.little);
                 let tp_lrot = Quat::from_rotation_x(curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -826,6 +1013,61 @@ pub fn update_hand_bones_emulated(
     }
 }
 
+/// poses the emulated hand with the local joint rotations of `pose` instead of curling it with the
+/// controller input, `pose` has to be for `hand`
+pub fn update_hand_bones_from_pose(
+    controller_transform: Transform,
+    hand: Hand,
+    pose: &HandPose,
+    hand_bone_query: &mut Query<(&mut Transform, &HandBone, &Hand)>,
+) {
+    let palm_quat = emulated_palm_rotation(controller_transform.rotation, hand);
+    let hand_transform_array: [Transform; 26] = get_simulated_open_hand_transforms(hand);
+    let mut calc_transforms = [Transform::default(); 26];
+    //parents come before their children in HandBone::ALL
+    for bone in HandBone::ALL {
+        let offset = hand_transform_array[bone.joint()].translation;
+        calc_transforms[bone.joint()] = match bone.parent() {
+            Some(parent) => {
+                let parent = calc_transforms[parent.joint()];
+                Transform {
+                    translation: parent.translation + parent.rotation.mul_vec3(offset),
+                    rotation: parent.rotation.mul_quat(pose.rotation(bone)),
+                    ..default()
+                }
+            }
+            None => Transform {
+                translation: controller_transform.translation + offset,
+                rotation: palm_quat.mul_quat(pose.rotation(bone)),
+                ..default()
+            },
+        };
+    }
+
+    for (mut transform, handbone, bonehand) in hand_bone_query.iter_mut() {
+        if *bonehand == hand {
+            *transform = calc_transforms[handbone.joint()];
+        }
+    }
+}
+
+/// the palm orientation of an emulated hand held by a controller
+fn emulated_palm_rotation(controller_rotation: Quat, hand: Hand) -> Quat {
+    let left_hand_rot = Quat::from_rotation_y(180.0 * PI / 180.0);
+    let controller_quat: Quat = match hand {
+        Hand::Left => controller_rotation.mul_quat(left_hand_rot),
+        Hand::Right => controller_rotation,
+    };
+    let y = Quat::from_rotation_y(-90.0 * PI / 180.0);
+    let x = Quat::from_rotation_x(-90.0 * PI / 180.0);
+    controller_quat.mul_quat(y).mul_quat(x)
+}
+
+/// the controller rotation that puts an emulated palm at `palm_rotation`
+fn emulated_controller_rotation(palm_rotation: Quat, hand: Hand) -> Quat {
+    palm_rotation.mul_quat(emulated_palm_rotation(Quat::IDENTITY, hand).inverse())
+}
+
 fn match_index(handbone: &HandBone) -> HandJoint {
     match handbone {
         HandBone::Palm => HandJoint::PALM,
@@ -857,17 +1099,21 @@ fn match_index(handbone: &HandBone) -> HandJoint {
     }
 }
 
-fn get_bone_curl_angle(bone: HandJoint, curl: f32) -> f32 {
-    let mul: f32 = match bone {
-        HandJoint::INDEX_PROXIMAL => 0.0,
-        HandJoint::MIDDLE_PROXIMAL => 0.0,
-        HandJoint::RING_PROXIMAL => 0.0,
-        HandJoint::LITTLE_PROXIMAL => 0.0,
-        HandJoint::THUMB_PROXIMAL => 0.0,
-        _ => 1.0,
+fn get_bone_curl_angle(bone: HandJoint, curl: f32, curve: &FingerCurve) -> f32 {
+    let segment = match bone {
+        HandJoint::INDEX_PROXIMAL => 0,
+        HandJoint::MIDDLE_PROXIMAL => 0,
+        HandJoint::RING_PROXIMAL => 0,
+        HandJoint::LITTLE_PROXIMAL => 0,
+        HandJoint::THUMB_PROXIMAL => 0,
+        HandJoint::INDEX_INTERMEDIATE => 1,
+        HandJoint::MIDDLE_INTERMEDIATE => 1,
+        HandJoint::RING_INTERMEDIATE => 1,
+        HandJoint::LITTLE_INTERMEDIATE => 1,
+        HandJoint::THUMB_DISTAL => 1,
+        _ => 2,
     };
-    let curl_angle = -((mul * curl * 80.0) + 5.0);
-    return curl_angle;
+    -curve.angle(segment, curl)
 }
 
 fn log_hand(hand_pose: [Posef; 26]) {
@@ -1005,60 +1251,177 @@ fn log_hand(hand_pose: [Posef; 26]) {
     );
 }
 
+/// picks the source for `hand` this frame, a hand that is neither tracked nor held keeps its source
+fn resolve_hand_input_source(
+    setting: HandInputSource,
+    current: HandInputSource,
+    joints_tracked: bool,
+    controller_tracked: bool,
+) -> HandInputSource {
+    match setting {
+        HandInputSource::Automatic => match (joints_tracked, controller_tracked) {
+            (true, _) => HandInputSource::OpenXr,
+            (false, true) => HandInputSource::Emulated,
+            (false, false) => current,
+        },
+        source => source,
+    }
+}
+
 pub fn update_hand_skeletons(
-    right_controller_query: Query<(&GlobalTransform, With<OpenXRRightController>)>,
-    left_controller_query: Query<(&GlobalTransform, With<OpenXRLeftController>)>,
+    right_controller_query: Query<
+        (
+            &GlobalTransform,
+            &Transform,
+            Option<&PalmPose>,
+            Option<&HandPoseBlend>,
+        ),
+        (With<OpenXRRightController>, Without<HandBone>),
+    >,
+    left_controller_query: Query<
+        (
+            &GlobalTransform,
+            &Transform,
+            Option<&PalmPose>,
+            Option<&HandPoseBlend>,
+        ),
+        (With<OpenXRLeftController>, Without<HandBone>),
+    >,
     hand_states_option: Option<ResMut<HandStatesResource>>,
     mut hand_bone_query: Query<(&mut Transform, &HandBone, &Hand)>,
+    mut radius_query: Query<&mut HandBoneRadius>,
+    mut velocity_query: Query<&mut XrVelocity, With<HandBone>>,
     input_source: Option<Res<HandInputSource>>,
+    mut active_sources: ResMut<ActiveHandInputSources>,
+    mut source_changed: EventWriter<HandInputSourceChanged>,
+    hands_resource: Option<Res<HandsResource>>,
+    tracked_joints: Res<TrackedHandJoints>,
+    mut data_sources: ResMut<HandDataSources>,
+    tracking_states: Option<Res<XrTrackingStates>>,
+    hand_poses: Option<Res<Assets<HandPose>>>,
+    curl_settings: Res<HandCurlSettings>,
 ) {
-    match input_source {
-        Some(res) => match *res {
-            HandInputSource::Emulated => {
-                // info!("hand input source is emulated");
-                match hand_states_option {
-                    Some(hands) => {
-                        let left_hand_transform = left_controller_query
-                            .get_single()
-                            .unwrap()
-                            .0
-                            .compute_transform();
-                        update_hand_bones_emulated(
-                            left_hand_transform,
-                            Hand::Left,
-                            hands.left,
-                            &mut hand_bone_query,
-                        );
-                        let right_hand_transform = right_controller_query
-                            .get_single()
-                            .unwrap()
-                            .0
-                            .compute_transform();
-                        update_hand_bones_emulated(
-                            right_hand_transform,
-                            Hand::Right,
-                            hands.right,
-                            &mut hand_bone_query,
-                        );
+    let setting = match input_source {
+        Some(res) => *res,
+        None => {
+            info!("hand input source not initialized");
+            return;
+        }
+    };
+    for hand in [Hand::Left, Hand::Right] {
+        //untracked hands have no joints and keep their last pose
+        let joints = tracked_joints.get(hand);
+        //without tracking states, e.g. in the simulator, the controllers are always there
+        let controller_tracked = match &tracking_states {
+            Some(states) => states.get(hand.into()).is_valid(),
+            None => true,
+        };
+        let current = active_sources.get(hand);
+        let source =
+            resolve_hand_input_source(setting, current, joints.is_some(), controller_tracked);
+        if source != current {
+            *active_sources.get_mut(hand) = source;
+            source_changed.send(HandInputSourceChanged { hand, source });
+            //the last tracked velocities don't apply to the emulated hand
+            if let Some(hands) = &hands_resource {
+                for bone in HandBone::ALL {
+                    if let Ok(mut velocity) = velocity_query.get_mut(hands.get(hand).entity(bone)) {
+                        *velocity = XrVelocity::default();
                     }
-                    None => info!("hand states resource not initialized yet"),
                 }
             }
+        }
+        //a tracked hand is optical unless the runtime says otherwise
+        *data_sources.get_mut(hand) = match source {
+            HandInputSource::OpenXr => joints
+                .and_then(|joints| joints.data_source)
+                .unwrap_or(HandDataSource::Optical),
+            _ => HandDataSource::Emulated,
+        };
+        match source {
             HandInputSource::OpenXr => {
-                info!("hand input source is open XR: this is not implemented yet");
-                return;
+                if let (Some(joints), Some(hands)) = (joints, &hands_resource) {
+                    update_hand_bones_openxr(
+                        hand,
+                        joints,
+                        hands,
+                        &mut hand_bone_query,
+                        &mut radius_query,
+                        &mut velocity_query,
+                    );
+                }
+            }
+            _ => {
+                let hand_states = match &hand_states_option {
+                    Some(hand_states) => hand_states,
+                    None => {
+                        info!("hand states resource not initialized yet");
+                        continue;
+                    }
+                };
+                let controller = match hand {
+                    Hand::Left => left_controller_query.get_single(),
+                    Hand::Right => right_controller_query.get_single(),
+                };
+                if let Ok((controller_global, controller_local, palm, blend)) = controller {
+                    //with a palm pose the emulated palm is put on it instead of the grip
+                    let controller_transform = match palm.and_then(|palm| palm.0) {
+                        Some(palm) => {
+                            let palm = GlobalTransform::from(
+                                controller_global.affine()
+                                    * controller_local.compute_affine().inverse()
+                                    * palm.compute_affine(),
+                            )
+                            .compute_transform();
+                            Transform {
+                                translation: palm.translation,
+                                rotation: emulated_controller_rotation(palm.rotation, hand),
+                                ..default()
+                            }
+                        }
+                        None => controller_global.compute_transform(),
+                    };
+                    //an authored pose replaces the procedural curl while it is there
+                    let pose = match (blend, &hand_poses) {
+                        (Some(blend), Some(hand_poses)) => blend.blend(hand, hand_poses),
+                        _ => None,
+                    };
+                    match pose {
+                        Some(pose) => update_hand_bones_from_pose(
+                            controller_transform,
+                            hand,
+                            &pose,
+                            &mut hand_bone_query,
+                        ),
+                        None => {
+                            let hand_state = match hand {
+                                Hand::Left => hand_states.left,
+                                Hand::Right => hand_states.right,
+                            };
+                            update_hand_bones_emulated(
+                                controller_transform,
+                                hand,
+                                hand_state,
+                                &curl_settings,
+                                &mut hand_bone_query,
+                            );
+                        }
+                    }
+                }
             }
-        },
-        None => {
-            info!("hand input source not initialized");
-            return;
         }
     }
 }
 
-pub fn draw_hand_entities(mut gizmos: Gizmos, query: Query<(&Transform, &HandBone)>) {
-    for (transform, hand_bone) in query.iter() {
+pub fn draw_hand_entities(
+    mut gizmos: Gizmos,
+    query: Query<(&Transform, &HandBone, Option<&HandBoneRadius>)>,
+) {
+    for (transform, hand_bone, bone_radius) in query.iter() {
         let (radius, color) = get_bone_gizmo_style(hand_bone);
+        let radius = bone_radius
+            .map(|bone_radius| bone_radius.0)
+            .unwrap_or(radius);
         gizmos.sphere(transform.translation, transform.rotation, radius, color);
     }
 }
@@ -1093,3 +1456,106 @@ fn get_bone_gizmo_style(hand_bone: &HandBone) -> (f32, Color) {
         HandBone::LittleTip => (0.002, Color::BLUE),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curl(value: f32) -> FingerCurl {
+        FingerCurl {
+            thumb: value,
+            index: value,
+            middle: value,
+            ring: value,
+            little: value,
+        }
+    }
+
+    #[test]
+    fn curve_maps_curl_to_the_open_and_closed_angles() {
+        let curve = FingerCurve {
+            open: [0.0, 10.0, 20.0],
+            closed: [90.0, 80.0, 70.0],
+            exponent: 2.0,
+        };
+        for segment in 0..3 {
+            assert_eq!(curve.angle(segment, 0.0), curve.open[segment]);
+            assert_eq!(curve.angle(segment, 1.0), curve.closed[segment]);
+            //out of range curls are clamped
+            assert_eq!(curve.angle(segment, -1.0), curve.open[segment]);
+            assert_eq!(curve.angle(segment, 2.0), curve.closed[segment]);
+        }
+        //the exponent keeps the finger open longer
+        assert_eq!(curve.angle(0, 0.5), 22.5);
+    }
+
+    #[test]
+    fn released_controller_opens_the_hand() {
+        let settings = HandCurlSettings::default();
+        assert_eq!(
+            settings.target_curl(&XrControllerInput::default()),
+            curl(0.0)
+        );
+    }
+
+    #[test]
+    fn full_input_closes_the_hand() {
+        let settings = HandCurlSettings::default();
+        let input = XrControllerInput {
+            trigger: 1.0,
+            trigger_touched: true,
+            squeeze: 1.0,
+            primary_button: true,
+            primary_touched: true,
+            ..default()
+        };
+        assert_eq!(
+            settings.target_curl(&input),
+            FingerCurl {
+                thumb: settings.thumb_pressed_curl,
+                ..curl(1.0)
+            }
+        );
+    }
+
+    #[test]
+    fn touch_and_proximity_curl_partially() {
+        let settings = HandCurlSettings::default();
+        let touched = XrControllerInput {
+            trigger_touched: true,
+            thumbrest_touched: true,
+            ..default()
+        };
+        let target = settings.target_curl(&touched);
+        assert_eq!(target.index, settings.trigger_touched_curl);
+        assert_eq!(target.thumb, settings.thumb_touched_curl);
+        let near = XrControllerInput {
+            trigger_proximity: Some(true),
+            thumb_proximity: Some(true),
+            ..default()
+        };
+        let target = settings.target_curl(&near);
+        assert_eq!(target.index, settings.proximity_curl);
+        assert_eq!(target.thumb, settings.proximity_curl);
+    }
+
+    #[test]
+    fn zero_smoothing_follows_the_input() {
+        let settings = HandCurlSettings {
+            smoothing: 0.0,
+            ..default()
+        };
+        assert_eq!(settings.smooth(curl(0.0), curl(1.0), 0.0), curl(1.0));
+        assert_eq!(settings.smooth(curl(1.0), curl(0.0), 0.016), curl(0.0));
+    }
+
+    #[test]
+    fn smoothing_moves_towards_the_input() {
+        let settings = HandCurlSettings::default();
+        assert_eq!(settings.smooth(curl(0.0), curl(1.0), 0.0), curl(0.0));
+        let step = settings.smooth(curl(0.0), curl(1.0), settings.smoothing);
+        assert!((step.index - (1.0 - (-1.0f32).exp())).abs() < 1e-6);
+        let settled = settings.smooth(curl(0.0), curl(1.0), 10.0);
+        assert!((settled.index - 1.0).abs() < 1e-6);
+    }
+}
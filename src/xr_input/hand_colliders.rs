@@ -0,0 +1,460 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use super::{
+    hand::{HandBone, HandBoneRadius, HandsResource},
+    interactions::{
+        update_interactable_states, InteractionEvent, XRInteractable, XRInteractableState,
+    },
+    Hand,
+};
+
+/// Gives every `HandBone` entity an [`XrHandCollider`] so hands can touch things without
+/// controllers.
+///
+/// Overlaps are tested by the plugin itself against entities with an [`XrCollider`] and sent as
+/// [`HandContactEvent`]s, the fingertips poke `XRInteractable` entities like a direct interactor.
+/// The colliders follow the joints' `GlobalTransform` once it is propagated, so like every other
+/// interactor they are tested against the previous frame's transforms.
+///
+/// There is no rapier or xpbd feature, their releases are built against the crates.io bevy while
+/// this crate follows bevy's git, so enabling one would pull in a second bevy. Apps using a physics
+/// engine can copy each [`XrHandCollider`] into a kinematic collider of their own.
+#[derive(Default)]
+pub struct XrHandCollidersPlugin;
+
+impl Plugin for XrHandCollidersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<XrHandColliderSettings>()
+            .init_resource::<HandContacts>()
+            .add_event::<HandContactEvent>()
+            .add_systems(PreUpdate, attach_hand_colliders)
+            .add_systems(
+                Update,
+                (
+                    update_hand_contacts,
+                    hand_poke_interactions.before(update_interactable_states),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                update_hand_colliders.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct XrHandColliderSettings {
+    /// joint radii below this are grown to it, the runtime radii of fingertips are tiny
+    pub min_radius: f32,
+    pub palm_radius: f32,
+    /// how far from an interactable a fingertip starts hovering it
+    pub poke_hover_distance: f32,
+    /// the radius of interactables without an `XrCollider`, the same the direct interactor uses
+    pub interactable_radius: f32,
+}
+
+impl Default for XrHandColliderSettings {
+    fn default() -> Self {
+        Self {
+            min_radius: 0.006,
+            palm_radius: 0.035,
+            poke_hover_distance: 0.02,
+            interactable_radius: 0.1,
+        }
+    }
+}
+
+/// a collider the hand joints are tested against, centered on the entity
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum XrCollider {
+    Sphere { radius: f32 },
+    Cuboid { half_extents: Vec3 },
+}
+
+impl XrCollider {
+    /// the distance from `point` to the surface, 0.0 inside
+    pub fn distance(&self, transform: &GlobalTransform, point: Vec3) -> f32 {
+        self.segment_distance(transform, point, point)
+    }
+
+    /// the distance from the segment between `start` and `end` to the surface, 0.0 when it
+    /// touches the inside
+    pub fn segment_distance(&self, transform: &GlobalTransform, start: Vec3, end: Vec3) -> f32 {
+        let transform = transform.compute_transform();
+        match self {
+            XrCollider::Sphere { radius } => {
+                let closest = closest_point_on_segment(start, end, transform.translation);
+                (closest.distance(transform.translation) - radius).max(0.0)
+            }
+            XrCollider::Cuboid { half_extents } => {
+                let inverse = transform.rotation.inverse();
+                let start = inverse * (start - transform.translation);
+                let end = inverse * (end - transform.translation);
+                let distance = |t: f32| {
+                    (start.lerp(end, t).abs() - *half_extents)
+                        .max(Vec3::ZERO)
+                        .length()
+                };
+                //the distance along the segment is convex, so a ternary search finds the closest
+                //point
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..SEGMENT_SEARCH_STEPS {
+                    let a = low + (high - low) / 3.0;
+                    let b = high - (high - low) / 3.0;
+                    if distance(a) <= distance(b) {
+                        high = b;
+                    } else {
+                        low = a;
+                    }
+                }
+                distance((low + high) * 0.5)
+            }
+        }
+    }
+}
+
+/// the iterations of the closest point search between a segment and a cuboid, the remaining
+/// interval is `(2/3)^n` of the segment
+const SEGMENT_SEARCH_STEPS: usize = 40;
+
+/// the point of the segment between `start` and `end` closest to `point`
+fn closest_point_on_segment(start: Vec3, end: Vec3, point: Vec3) -> Vec3 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared > 0.0 {
+        let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+        start + segment * t
+    } else {
+        start
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrHandColliderShape {
+    Sphere,
+    /// a capsule from the joint to its parent joint
+    Capsule,
+}
+
+/// the collider of a hand joint, `start`, `end` and `radius` are updated every frame in world
+/// space
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XrHandCollider {
+    pub shape: XrHandColliderShape,
+    pub start: Vec3,
+    pub end: Vec3,
+    pub radius: f32,
+}
+
+impl XrHandCollider {
+    pub fn new(shape: XrHandColliderShape) -> Self {
+        Self {
+            shape,
+            start: Vec3::ZERO,
+            end: Vec3::ZERO,
+            radius: 0.0,
+        }
+    }
+
+    /// spheres for the palm, wrist and metacarpals, capsules along the finger bones
+    pub fn for_bone(bone: HandBone) -> Self {
+        match bone {
+            HandBone::Palm
+            | HandBone::Wrist
+            | HandBone::ThumbMetacarpal
+            | HandBone::IndexMetacarpal
+            | HandBone::MiddleMetacarpal
+            | HandBone::RingMetacarpal
+            | HandBone::LittleMetacarpal => Self::new(XrHandColliderShape::Sphere),
+            _ => Self::new(XrHandColliderShape::Capsule),
+        }
+    }
+
+    /// the point of the collider's core closest to `point`
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        closest_point_on_segment(self.start, self.end, point)
+    }
+
+    /// how far the collider is from the surface of `collider`, 0.0 when they overlap
+    pub fn distance_to(&self, collider: &XrCollider, transform: &GlobalTransform) -> f32 {
+        (collider.segment_distance(transform, self.start, self.end) - self.radius).max(0.0)
+    }
+}
+
+/// a fingertip that pokes `XRInteractable` entities, it selects what it touches and hovers what
+/// it is near
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct XrPokeInteractor;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandContactPhase {
+    Started,
+    Ended,
+}
+
+/// sent when a hand joint starts or stops touching an entity with an `XrCollider`
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandContactEvent {
+    pub hand: Hand,
+    pub bone: HandBone,
+    /// the `HandBone` entity
+    pub joint: Entity,
+    pub entity: Entity,
+    pub phase: HandContactPhase,
+}
+
+/// the joint and entity pairs touching this frame
+#[derive(Resource, Clone, Debug, Default)]
+pub struct HandContacts {
+    pub contacts: HashSet<(Entity, Entity)>,
+}
+
+impl HandContacts {
+    pub fn is_touching(&self, joint: Entity, entity: Entity) -> bool {
+        self.contacts.contains(&(joint, entity))
+    }
+}
+
+pub fn attach_hand_colliders(
+    mut commands: Commands,
+    bone_query: Query<(Entity, &HandBone), Added<HandBone>>,
+) {
+    for (entity, bone) in bone_query.iter() {
+        let mut joint = commands.entity(entity);
+        joint.insert(XrHandCollider::for_bone(*bone));
+        match bone {
+            HandBone::ThumbTip
+            | HandBone::IndexTip
+            | HandBone::MiddleTip
+            | HandBone::RingTip
+            | HandBone::LittleTip => {
+                joint.insert(XrPokeInteractor);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn update_hand_colliders(
+    settings: Res<XrHandColliderSettings>,
+    hands: Option<Res<HandsResource>>,
+    transform_query: Query<&GlobalTransform, With<HandBone>>,
+    mut collider_query: Query<(
+        &mut XrHandCollider,
+        &GlobalTransform,
+        &HandBone,
+        &Hand,
+        Option<&HandBoneRadius>,
+    )>,
+) {
+    let hands = match hands {
+        Some(hands) => hands,
+        None => return,
+    };
+    for (mut collider, transform, bone, hand, radius) in collider_query.iter_mut() {
+        collider.start = transform.translation();
+        collider.end = match (collider.shape, bone.parent()) {
+            (XrHandColliderShape::Capsule, Some(parent)) => transform_query
+                .get(hands.get(*hand).entity(parent))
+                .map(|parent| parent.translation())
+                .unwrap_or(collider.start),
+            _ => collider.start,
+        };
+        collider.radius = match bone {
+            HandBone::Palm => settings.palm_radius,
+            _ => radius
+                .map(|radius| radius.0)
+                .unwrap_or_default()
+                .max(settings.min_radius),
+        };
+    }
+}
+
+pub fn update_hand_contacts(
+    mut contacts: ResMut<HandContacts>,
+    mut events: EventWriter<HandContactEvent>,
+    hand_collider_query: Query<(Entity, &XrHandCollider, &HandBone, &Hand)>,
+    collider_query: Query<(Entity, &XrCollider, &GlobalTransform)>,
+) {
+    let mut touching = HashSet::new();
+    for (joint, hand_collider, bone, hand) in hand_collider_query.iter() {
+        for (entity, collider, transform) in collider_query.iter() {
+            if hand_collider.distance_to(collider, transform) > 0.0 {
+                continue;
+            }
+            touching.insert((joint, entity));
+            if !contacts.is_touching(joint, entity) {
+                events.send(HandContactEvent {
+                    hand: *hand,
+                    bone: *bone,
+                    joint,
+                    entity,
+                    phase: HandContactPhase::Started,
+                });
+            }
+        }
+    }
+    for (joint, entity) in contacts.contacts.difference(&touching) {
+        if let Ok((_, _, bone, hand)) = hand_collider_query.get(*joint) {
+            events.send(HandContactEvent {
+                hand: *hand,
+                bone: *bone,
+                joint: *joint,
+                entity: *entity,
+                phase: HandContactPhase::Ended,
+            });
+        }
+    }
+    contacts.contacts = touching;
+}
+
+pub fn hand_poke_interactions(
+    settings: Res<XrHandColliderSettings>,
+    poke_query: Query<(Entity, &XrHandCollider), With<XrPokeInteractor>>,
+    interactable_query: Query<
+        (Entity, &GlobalTransform, Option<&XrCollider>),
+        With<XRInteractable>,
+    >,
+    mut writer: EventWriter<InteractionEvent>,
+) {
+    for (interactor, hand_collider) in poke_query.iter() {
+        for (interactable, transform, collider) in interactable_query.iter() {
+            let collider = collider.copied().unwrap_or(XrCollider::Sphere {
+                radius: settings.interactable_radius,
+            });
+            let distance = hand_collider.distance_to(&collider, transform);
+            let interactable_state = match distance {
+                d if d <= 0.0 => XRInteractableState::Select,
+                d if d <= settings.poke_hover_distance => XRInteractableState::Hover,
+                _ => continue,
+            };
+            writer.send(InteractionEvent {
+                interactor,
+                interactable,
+                interactable_state,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use bevy::transform::TransformPlugin;
+
+    use super::*;
+
+    fn capsule(start: Vec3, end: Vec3, radius: f32) -> XrHandCollider {
+        XrHandCollider {
+            shape: XrHandColliderShape::Capsule,
+            start,
+            end,
+            radius,
+        }
+    }
+
+    fn assert_distance(
+        collider: &XrHandCollider,
+        target: &XrCollider,
+        transform: Transform,
+        expected: f32,
+    ) {
+        let distance = collider.distance_to(target, &GlobalTransform::from(transform));
+        assert!(
+            (distance - expected).abs() < 1e-4,
+            "expected {expected}, got {distance}"
+        );
+    }
+
+    #[test]
+    fn capsule_to_sphere() {
+        let sphere = XrCollider::Sphere { radius: 0.1 };
+        let transform = Transform::from_xyz(0.0, 1.0, 0.0);
+        //passing over the sphere
+        let over = capsule(Vec3::new(-1.0, 1.3, 0.0), Vec3::new(1.0, 1.3, 0.0), 0.05);
+        assert_distance(&over, &sphere, transform, 0.15);
+        //pointing away from it
+        let away = capsule(Vec3::new(0.5, 1.0, 0.0), Vec3::new(1.0, 1.0, 0.0), 0.05);
+        assert_distance(&away, &sphere, transform, 0.35);
+        let touching = capsule(Vec3::new(0.0, 1.0, -1.0), Vec3::new(0.0, 1.0, 1.0), 0.01);
+        assert_distance(&touching, &sphere, transform, 0.0);
+    }
+
+    #[test]
+    fn capsule_to_cuboid() {
+        let cuboid = XrCollider::Cuboid {
+            half_extents: Vec3::new(1.0, 0.01, 1.0),
+        };
+        let transform = Transform::IDENTITY;
+        //flat above the box
+        let above = capsule(Vec3::new(-3.0, 0.05, 0.0), Vec3::new(3.0, 0.05, 0.0), 0.01);
+        assert_distance(&above, &cuboid, transform, 0.03);
+        //beside its edge
+        let beside = capsule(Vec3::new(1.5, 0.0, -1.0), Vec3::new(1.5, 0.0, 1.0), 0.01);
+        assert_distance(&beside, &cuboid, transform, 0.49);
+        //nearly flat, crossing the box away from its center, the point closest to the center is
+        //above the box
+        let crossing = capsule(Vec3::new(0.9, -0.02, 0.0), Vec3::new(-3.0, 0.3, 0.0), 0.005);
+        assert_distance(&crossing, &cuboid, transform, 0.0);
+    }
+
+    #[test]
+    fn colliders_follow_a_moved_tracking_root() {
+        let mut app = App::new();
+        app.add_plugins(TransformPlugin)
+            .init_resource::<XrHandColliderSettings>()
+            .add_systems(
+                PostUpdate,
+                update_hand_colliders.after(TransformSystem::TransformPropagate),
+            );
+        let root = app
+            .world
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                5.0, 0.0, 0.0,
+            )))
+            .id();
+        let mut joint = |bone: HandBone, translation: Vec3| {
+            app.world
+                .spawn((
+                    TransformBundle::from_transform(Transform::from_translation(translation)),
+                    bone,
+                    Hand::Left,
+                    XrHandCollider::for_bone(bone),
+                ))
+                .set_parent(root)
+                .id()
+        };
+        let distal = joint(HandBone::IndexDistal, Vec3::new(0.0, 1.0, 0.0));
+        let tip = joint(HandBone::IndexTip, Vec3::new(0.0, 1.0, -0.02));
+        let mut hands = HandsResource::default();
+        hands.left.index.distal = distal;
+        hands.left.index.tip = tip;
+        app.insert_resource(hands);
+        app.update();
+
+        let collider = *app.world.get::<XrHandCollider>(tip).unwrap();
+        assert_eq!(collider.start, Vec3::new(5.0, 1.0, -0.02));
+        assert_eq!(collider.end, Vec3::new(5.0, 1.0, 0.0));
+        let sphere = XrCollider::Sphere { radius: 0.1 };
+        assert_distance(&collider, &sphere, Transform::from_xyz(5.0, 1.0, 0.0), 0.0);
+        assert_distance(
+            &collider,
+            &sphere,
+            Transform::from_xyz(0.0, 1.0, 0.0),
+            4.9 - collider.radius,
+        );
+    }
+
+    #[test]
+    fn capsule_to_rotated_cuboid() {
+        let cuboid = XrCollider::Cuboid {
+            half_extents: Vec3::new(1.0, 0.01, 1.0),
+        };
+        //stood on its side, thin along x
+        let transform = Transform::from_rotation(Quat::from_rotation_z(FRAC_PI_2));
+        let along = capsule(Vec3::new(0.05, -3.0, 0.0), Vec3::new(0.05, 3.0, 0.0), 0.01);
+        assert_distance(&along, &cuboid, transform, 0.03);
+    }
+}
@@ -0,0 +1,526 @@
+use std::ptr;
+
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy::render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::transform::TransformSystem;
+use bevy::utils::HashMap;
+use openxr::sys;
+
+use crate::resources::XrInstance;
+
+use super::{
+    hand::{HandBone, HandsResource},
+    hand_tracking::HandTrackers,
+    trackers::OpenXRTrackingRoot,
+    Hand, QuatConv, Vec3Conv,
+};
+
+/// Skins hand models to the `HandBone` entities spawned by `OpenXrHandInput`.
+///
+/// Spawn an [`XrHandMeshBundle`] with a rigged glTF scene, its joints are found by name once the
+/// scene spawned. [`XrRuntimeHandMeshBundle`] uses the runtime's own hand mesh from
+/// `XR_FB_hand_tracking_mesh` instead, on runtimes without it nothing is shown.
+///
+/// Emulated hands only move the joints, so the meshes look best with tracked hands.
+#[derive(Default)]
+pub struct XrHandMeshPlugin;
+
+impl Plugin for XrHandMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (bind_hand_mesh_joints, load_runtime_hand_meshes))
+            .add_systems(
+                PostUpdate,
+                update_hand_mesh_joints.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// maps the joint names of a rig to `HandBone`s, joints without a name stay in their bind pose
+#[derive(Clone, Debug)]
+pub struct HandJointNames(pub Vec<(HandBone, String)>);
+
+impl Default for HandJointNames {
+    fn default() -> Self {
+        Self::webxr()
+    }
+}
+
+impl HandJointNames {
+    /// the WebXR joint names, used by the webxr-input-profiles hand models
+    pub fn webxr() -> Self {
+        Self(
+            [
+                (HandBone::Wrist, "wrist"),
+                (HandBone::ThumbMetacarpal, "thumb-metacarpal"),
+                (HandBone::ThumbProximal, "thumb-phalanx-proximal"),
+                (HandBone::ThumbDistal, "thumb-phalanx-distal"),
+                (HandBone::ThumbTip, "thumb-tip"),
+                (HandBone::IndexMetacarpal, "index-finger-metacarpal"),
+                (HandBone::IndexProximal, "index-finger-phalanx-proximal"),
+                (
+                    HandBone::IndexIntermediate,
+                    "index-finger-phalanx-intermediate",
+                ),
+                (HandBone::IndexDistal, "index-finger-phalanx-distal"),
+                (HandBone::IndexTip, "index-finger-tip"),
+                (HandBone::MiddleMetacarpal, "middle-finger-metacarpal"),
+                (HandBone::MiddleProximal, "middle-finger-phalanx-proximal"),
+                (
+                    HandBone::MiddleIntermediate,
+                    "middle-finger-phalanx-intermediate",
+                ),
+                (HandBone::MiddleDistal, "middle-finger-phalanx-distal"),
+                (HandBone::MiddleTip, "middle-finger-tip"),
+                (HandBone::RingMetacarpal, "ring-finger-metacarpal"),
+                (HandBone::RingProximal, "ring-finger-phalanx-proximal"),
+                (
+                    HandBone::RingIntermediate,
+                    "ring-finger-phalanx-intermediate",
+                ),
+                (HandBone::RingDistal, "ring-finger-phalanx-distal"),
+                (HandBone::RingTip, "ring-finger-tip"),
+                (HandBone::LittleMetacarpal, "pinky-finger-metacarpal"),
+                (HandBone::LittleProximal, "pinky-finger-phalanx-proximal"),
+                (
+                    HandBone::LittleIntermediate,
+                    "pinky-finger-phalanx-intermediate",
+                ),
+                (HandBone::LittleDistal, "pinky-finger-phalanx-distal"),
+                (HandBone::LittleTip, "pinky-finger-tip"),
+            ]
+            .into_iter()
+            .map(|(bone, name)| (bone, name.to_string()))
+            .collect(),
+        )
+    }
+
+    pub fn bone(&self, name: &str) -> Option<HandBone> {
+        self.0
+            .iter()
+            .find(|(_, joint_name)| joint_name == name)
+            .map(|(bone, _)| *bone)
+    }
+}
+
+/// a rigged hand model that follows `hand`
+#[derive(Component, Clone, Debug)]
+pub struct XrHandMesh {
+    pub hand: Hand,
+    pub joint_names: HandJointNames,
+    /// the hand the model was made for, the model is mirrored when it differs from `hand`
+    pub model_hand: Hand,
+    /// turns the OpenXR joint axes, -Z along the finger and +Y out of the back of the hand, into
+    /// the axes of the rig's joints
+    pub joint_rotation: Quat,
+}
+
+#[derive(Bundle)]
+pub struct XrHandMeshBundle {
+    pub scene: SceneBundle,
+    pub hand_mesh: XrHandMesh,
+}
+
+impl XrHandMeshBundle {
+    pub fn new(hand: Hand, scene: Handle<Scene>) -> Self {
+        Self {
+            scene: SceneBundle { scene, ..default() },
+            hand_mesh: XrHandMesh {
+                hand,
+                joint_names: HandJointNames::default(),
+                model_hand: hand,
+                joint_rotation: Quat::IDENTITY,
+            },
+        }
+    }
+
+    pub fn with_joint_names(mut self, joint_names: HandJointNames) -> Self {
+        self.hand_mesh.joint_names = joint_names;
+        self
+    }
+
+    pub fn with_model_hand(mut self, model_hand: Hand) -> Self {
+        self.hand_mesh.model_hand = model_hand;
+        self
+    }
+
+    pub fn with_joint_rotation(mut self, joint_rotation: Quat) -> Self {
+        self.hand_mesh.joint_rotation = joint_rotation;
+        self
+    }
+}
+
+/// a joint of an `XrHandMesh` rig that follows a `HandBone`
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XrHandMeshJoint {
+    pub hand: Hand,
+    pub bone: HandBone,
+    pub rotation: Quat,
+    pub mirror: bool,
+}
+
+/// added to an `XrHandMesh` once its joints were found
+#[derive(Component)]
+pub struct XrHandMeshBound;
+
+/// finds the joints of newly spawned hand scenes by name
+pub fn bind_hand_mesh_joints(
+    mut commands: Commands,
+    hand_mesh_query: Query<(Entity, &XrHandMesh), Without<XrHandMeshBound>>,
+    children_query: Query<&Children>,
+    name_query: Query<&Name>,
+    mesh_query: Query<&Handle<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (entity, hand_mesh) in hand_mesh_query.iter() {
+        let mirror = hand_mesh.model_hand != hand_mesh.hand;
+        let mut bound = 0;
+        for descendant in children_query.iter_descendants(entity) {
+            let bone = match name_query
+                .get(descendant)
+                .ok()
+                .and_then(|name| hand_mesh.joint_names.bone(name.as_str()))
+            {
+                Some(bone) => bone,
+                None => continue,
+            };
+            commands.entity(descendant).insert(XrHandMeshJoint {
+                hand: hand_mesh.hand,
+                bone,
+                rotation: hand_mesh.joint_rotation,
+                mirror,
+            });
+            bound += 1;
+        }
+        //the scene isn't spawned yet
+        if bound == 0 {
+            continue;
+        }
+        //mirroring the joints turns the triangles inside out, so mirrored copies of the meshes
+        //get their winding flipped, the meshes may be shared with an unmirrored hand
+        if mirror {
+            for descendant in children_query.iter_descendants(entity) {
+                let mut mesh = match mesh_query.get(descendant).ok().and_then(|m| meshes.get(m)) {
+                    Some(mesh) => mesh.clone(),
+                    None => continue,
+                };
+                if let Some(indices) = mesh.indices_mut() {
+                    flip_winding(indices);
+                }
+                commands.entity(descendant).insert(meshes.add(mesh));
+            }
+        }
+        if bound < hand_mesh.joint_names.0.len() {
+            warn!(
+                "only found {} of {} hand joints in the hand mesh",
+                bound,
+                hand_mesh.joint_names.0.len()
+            );
+        }
+        commands.entity(entity).insert(XrHandMeshBound);
+    }
+}
+
+/// reverses the order of every triangle, turning front faces into back faces
+pub fn flip_winding(indices: &mut Indices) {
+    match indices {
+        Indices::U16(indices) => indices.chunks_exact_mut(3).for_each(|t| t.swap(1, 2)),
+        Indices::U32(indices) => indices.chunks_exact_mut(3).for_each(|t| t.swap(1, 2)),
+    }
+}
+
+/// moves the rig joints onto their `HandBone`s
+pub fn update_hand_mesh_joints(
+    hands: Option<Res<HandsResource>>,
+    tracking_root_query: Query<&GlobalTransform, With<OpenXRTrackingRoot>>,
+    hand_bone_query: Query<&Transform, (With<HandBone>, Without<XrHandMeshJoint>)>,
+    mut joint_query: Query<(Entity, &XrHandMeshJoint, &Parent, &mut Transform)>,
+    global_query: Query<&GlobalTransform>,
+) {
+    let hands = match hands {
+        Some(hands) => hands,
+        None => return,
+    };
+    let root = tracking_root_query
+        .get_single()
+        .map(|root| root.affine())
+        .unwrap_or_default();
+    //the hand bones are in tracking root space, work out where every joint should end up first
+    let mut targets = HashMap::new();
+    for (entity, joint, _, _) in joint_query.iter() {
+        let bone = match hand_bone_query.get(hands.get(joint.hand).entity(joint.bone)) {
+            Ok(bone) => bone,
+            Err(_) => continue,
+        };
+        let mut target = root * bone.compute_affine() * Affine3A::from_quat(joint.rotation);
+        if joint.mirror {
+            target = target * Affine3A::from_scale(Vec3::new(-1.0, 1.0, 1.0));
+        }
+        targets.insert(entity, target);
+    }
+    //then make them local to their parents, which may be joints that are moved this frame too
+    for (entity, _, parent, mut transform) in joint_query.iter_mut() {
+        let target = match targets.get(&entity) {
+            Some(target) => *target,
+            None => continue,
+        };
+        let parent_affine = match targets.get(&parent.get()) {
+            Some(parent_target) => *parent_target,
+            None => match global_query.get(parent.get()) {
+                Ok(parent_global) => parent_global.affine(),
+                Err(_) => continue,
+            },
+        };
+        *transform = Transform::from_matrix((parent_affine.inverse() * target).into());
+    }
+}
+
+/// shows the runtime's hand mesh, skinned to the `HandBone` entities
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XrRuntimeHandMesh {
+    pub hand: Hand,
+}
+
+#[derive(Bundle)]
+pub struct XrRuntimeHandMeshBundle {
+    pub spatial: SpatialBundle,
+    pub hand_mesh: XrRuntimeHandMesh,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl XrRuntimeHandMeshBundle {
+    pub fn new(hand: Hand, material: Handle<StandardMaterial>) -> Self {
+        Self {
+            spatial: SpatialBundle::default(),
+            hand_mesh: XrRuntimeHandMesh { hand },
+            material,
+        }
+    }
+}
+
+/// added once loading the runtime hand mesh was attempted, whether it worked or not
+#[derive(Component)]
+pub struct XrRuntimeHandMeshLoaded;
+
+pub fn hand_tracking_mesh_supported(instance: &XrInstance) -> bool {
+    instance.exts().fb_hand_tracking_mesh.is_some()
+}
+
+pub fn load_runtime_hand_meshes(
+    mut commands: Commands,
+    hand_mesh_query: Query<(Entity, &XrRuntimeHandMesh), Without<XrRuntimeHandMeshLoaded>>,
+    instance: Option<Res<XrInstance>>,
+    trackers: Option<Res<HandTrackers>>,
+    hands: Option<Res<HandsResource>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut inverse_bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+) {
+    let (instance, trackers, hands) = match (instance, trackers, hands) {
+        (Some(instance), Some(trackers), Some(hands)) => (instance, trackers, hands),
+        _ => return,
+    };
+    for (entity, hand_mesh) in hand_mesh_query.iter() {
+        commands.entity(entity).insert(XrRuntimeHandMeshLoaded);
+        let (mesh, bindposes) = match get_runtime_hand_mesh(&instance, &trackers, hand_mesh.hand) {
+            Some(mesh) => mesh,
+            None => {
+                info!("the runtime has no hand mesh for {:?}", hand_mesh.hand);
+                continue;
+            }
+        };
+        //the runtime's joints are in XR_EXT_hand_tracking order, like `HandBone::ALL`
+        let joints = HandBone::ALL
+            .into_iter()
+            .map(|bone| hands.get(hand_mesh.hand).entity(bone))
+            .collect();
+        commands.entity(entity).insert((
+            meshes.add(mesh),
+            SkinnedMesh {
+                inverse_bindposes: inverse_bindposes.add(bindposes.into()),
+                joints,
+            },
+        ));
+    }
+}
+
+fn empty_hand_mesh() -> sys::HandTrackingMeshFB {
+    sys::HandTrackingMeshFB {
+        ty: sys::HandTrackingMeshFB::TYPE,
+        next: ptr::null_mut(),
+        joint_capacity_input: 0,
+        joint_count_output: 0,
+        joint_bind_poses: ptr::null_mut(),
+        joint_radii: ptr::null_mut(),
+        joint_parents: ptr::null_mut(),
+        vertex_capacity_input: 0,
+        vertex_count_output: 0,
+        vertex_positions: ptr::null_mut(),
+        vertex_normals: ptr::null_mut(),
+        vertex_uvs: ptr::null_mut(),
+        vertex_blend_indices: ptr::null_mut(),
+        vertex_blend_weights: ptr::null_mut(),
+        index_capacity_input: 0,
+        index_count_output: 0,
+        indices: ptr::null_mut(),
+    }
+}
+
+/// the mesh is skinned to the `HandBone` entities by joint index, so it needs one joint per bone
+fn runtime_hand_mesh_skinnable(joint_count: usize) -> bool {
+    joint_count == HandBone::ALL.len()
+}
+
+/// reads the mesh of `hand` from `XR_FB_hand_tracking_mesh`, along with the inverse bind poses
+pub fn get_runtime_hand_mesh(
+    instance: &XrInstance,
+    trackers: &HandTrackers,
+    hand: Hand,
+) -> Option<(Mesh, Vec<Mat4>)> {
+    let ext = instance.exts().fb_hand_tracking_mesh.as_ref()?;
+    let tracker = trackers.get(hand).as_raw();
+    //the first call only reports the sizes
+    let mut sizes = empty_hand_mesh();
+    let result = unsafe { (ext.get_hand_mesh)(tracker, &mut sizes) };
+    if result.into_raw() < 0 {
+        return None;
+    }
+    let joint_count = sizes.joint_count_output as usize;
+    let vertex_count = sizes.vertex_count_output as usize;
+    let index_count = sizes.index_count_output as usize;
+    if !runtime_hand_mesh_skinnable(joint_count) {
+        warn!(
+            "the runtime hand mesh has {} joints instead of {}, it can't be skinned",
+            joint_count,
+            HandBone::ALL.len()
+        );
+        return None;
+    }
+    let mut bind_poses = vec![openxr::Posef::IDENTITY; joint_count];
+    let mut radii = vec![0.0; joint_count];
+    let mut parents = vec![sys::HandJointEXT::from_raw(0); joint_count];
+    let mut positions = vec![sys::Vector3f::default(); vertex_count];
+    let mut normals = vec![sys::Vector3f::default(); vertex_count];
+    let mut uvs = vec![sys::Vector2f::default(); vertex_count];
+    let mut blend_indices = vec![sys::Vector4sFB::default(); vertex_count];
+    let mut blend_weights = vec![sys::Vector4f::default(); vertex_count];
+    let mut indices = vec![0i16; index_count];
+    let mut hand_mesh = sys::HandTrackingMeshFB {
+        joint_capacity_input: joint_count as u32,
+        joint_bind_poses: bind_poses.as_mut_ptr(),
+        joint_radii: radii.as_mut_ptr(),
+        joint_parents: parents.as_mut_ptr(),
+        vertex_capacity_input: vertex_count as u32,
+        vertex_positions: positions.as_mut_ptr(),
+        vertex_normals: normals.as_mut_ptr(),
+        vertex_uvs: uvs.as_mut_ptr(),
+        vertex_blend_indices: blend_indices.as_mut_ptr(),
+        vertex_blend_weights: blend_weights.as_mut_ptr(),
+        index_capacity_input: index_count as u32,
+        indices: indices.as_mut_ptr(),
+        ..empty_hand_mesh()
+    };
+    let result = unsafe { (ext.get_hand_mesh)(tracker, &mut hand_mesh) };
+    if result.into_raw() < 0 || hand_mesh.joint_count_output as usize != joint_count {
+        return None;
+    }
+    //only what the runtime wrote is used
+    let vertex_count = (hand_mesh.vertex_count_output as usize).min(vertex_count);
+    let index_count = (hand_mesh.index_count_output as usize).min(index_count);
+    positions.truncate(vertex_count);
+    normals.truncate(vertex_count);
+    uvs.truncate(vertex_count);
+    blend_indices.truncate(vertex_count);
+    blend_weights.truncate(vertex_count);
+    indices.truncate(index_count);
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions
+            .iter()
+            .map(|position| position.to_vec3().to_array())
+            .collect::<Vec<_>>(),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        normals
+            .iter()
+            .map(|normal| normal.to_vec3().to_array())
+            .collect::<Vec<_>>(),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        uvs.iter().map(|uv| [uv.x, uv.y]).collect::<Vec<_>>(),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_JOINT_INDEX,
+        VertexAttributeValues::Uint16x4(
+            blend_indices
+                .iter()
+                .map(|i| [i.x as u16, i.y as u16, i.z as u16, i.w as u16])
+                .collect(),
+        ),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_JOINT_WEIGHT,
+        blend_weights
+            .iter()
+            .map(|w| [w.x, w.y, w.z, w.w])
+            .collect::<Vec<_>>(),
+    );
+    mesh.set_indices(Some(Indices::U16(
+        indices.iter().map(|index| *index as u16).collect(),
+    )));
+    let inverse_bindposes = bind_poses
+        .iter()
+        .map(|pose| {
+            Mat4::from_rotation_translation(pose.orientation.to_quat(), pose.position.to_vec3())
+                .inverse()
+        })
+        .collect();
+    Some((mesh, inverse_bindposes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webxr_joint_names_follow_the_bone_order() {
+        //webxr has no palm joint, the rest is in XR_EXT_hand_tracking order
+        let bones: Vec<_> = HandJointNames::webxr()
+            .0
+            .into_iter()
+            .map(|(bone, _)| bone)
+            .collect();
+        assert_eq!(bones, HandBone::ALL[1..]);
+    }
+
+    #[test]
+    fn webxr_joint_names_are_unique() {
+        let names = HandJointNames::webxr();
+        for (bone, name) in names.0.iter() {
+            assert_eq!(names.bone(name), Some(*bone));
+        }
+    }
+
+    #[test]
+    fn only_runtime_meshes_with_a_joint_per_bone_are_skinned() {
+        assert!(runtime_hand_mesh_skinnable(26));
+        assert!(!runtime_hand_mesh_skinnable(0));
+        assert!(!runtime_hand_mesh_skinnable(25));
+        assert!(!runtime_hand_mesh_skinnable(27));
+    }
+
+    #[test]
+    fn flipped_triangles_are_wound_the_other_way() {
+        let mut indices = Indices::U16(vec![0, 1, 2, 2, 3, 0]);
+        flip_winding(&mut indices);
+        assert_eq!(indices.iter().collect::<Vec<_>>(), [0, 2, 1, 2, 0, 3]);
+        let mut indices = Indices::U32(vec![4, 5, 6]);
+        flip_winding(&mut indices);
+        flip_winding(&mut indices);
+        assert_eq!(indices.iter().collect::<Vec<_>>(), [4, 5, 6]);
+    }
+}
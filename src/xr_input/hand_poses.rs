@@ -1,7 +1,258 @@
-use bevy::prelude::{Quat, Transform, Vec3};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::{
+    info, warn, App, Asset, AssetApp, Assets, Component, Event, EventReader, Handle, Plugin, Quat,
+    Query, Res, Transform, TypePath, Update, Vec3, With,
+};
+use bevy::utils::BoxedFuture;
 use openxr::{Posef, Quaternionf, Vector3f};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    hand::{ActiveHandInputSources, HandBone, HandInputSource, HandsResource},
+    Hand,
+};
+
+/// Loads [`HandPose`] assets from `.handpose.ron` and `.handpose.json` files and captures the
+/// tracked hands into new ones.
+///
+/// Send a [`CaptureHandPose`] event while a hand is tracked to write its current pose to a file,
+/// insert a [`HandPoseBlend`] on a controller to show authored poses on its emulated hand.
+#[derive(Default)]
+pub struct XrHandPosePlugin;
+
+impl Plugin for XrHandPosePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<HandPose>()
+            .register_asset_loader(HandPoseLoader)
+            .add_event::<CaptureHandPose>()
+            .add_systems(Update, capture_hand_poses);
+    }
+}
+
+/// the local rotation of every joint relative to its parent joint, in `HandBone::ALL` order
+///
+/// the palm is relative to the hand itself, so an all identity pose is the flat open hand
+#[derive(Asset, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HandPose {
+    /// the hand the pose was authored for, the other hand gets it mirrored
+    pub hand: Hand,
+    pub rotations: Vec<[f32; 4]>,
+}
+
+impl HandPose {
+    pub fn open(hand: Hand) -> Self {
+        Self {
+            hand,
+            rotations: vec![Quat::IDENTITY.to_array(); HandBone::ALL.len()],
+        }
+    }
+
+    pub fn rotation(&self, bone: HandBone) -> Quat {
+        self.rotations
+            .get(bone as usize)
+            .map(|rotation| Quat::from_array(*rotation).normalize())
+            .unwrap_or(Quat::IDENTITY)
+    }
+
+    pub fn set_rotation(&mut self, bone: HandBone, rotation: Quat) {
+        self.rotations
+            .resize(HandBone::ALL.len(), Quat::IDENTITY.to_array());
+        self.rotations[bone as usize] = rotation.to_array();
+    }
+
+    /// the pose for `hand`, mirrored across the x axis when it was authored for the other hand
+    pub fn for_hand(&self, hand: Hand) -> HandPose {
+        let mut pose = HandPose::open(hand);
+        for bone in HandBone::ALL {
+            let rotation = self.rotation(bone);
+            let rotation = if self.hand == hand {
+                rotation
+            } else {
+                Quat::from_xyzw(rotation.x, -rotation.y, -rotation.z, rotation.w)
+            };
+            pose.set_rotation(bone, rotation);
+        }
+        pose
+    }
+
+    /// slerps every joint from this pose to `other`, `other` has to be for the same hand
+    pub fn lerp(&self, other: &HandPose, t: f32) -> HandPose {
+        let mut pose = HandPose::open(self.hand);
+        for bone in HandBone::ALL {
+            pose.set_rotation(bone, self.rotation(bone).slerp(other.rotation(bone), t));
+        }
+        pose
+    }
+
+    /// computes the local joint rotations from joint transforms that share one space, like the
+    /// tracked `HandBone` entities
+    pub fn from_joint_rotations(hand: Hand, rotation: impl Fn(HandBone) -> Quat) -> Self {
+        let mut pose = HandPose::open(hand);
+        for bone in HandBone::ALL {
+            if let Some(parent) = bone.parent() {
+                pose.set_rotation(bone, rotation(parent).inverse() * rotation(bone));
+            }
+        }
+        pose
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path.as_ref())?;
+        Self::from_bytes(&bytes, is_json(path.as_ref()))
+    }
 
-use super::Hand;
+    /// saves the pose as json when the path ends in `.json` and as ron otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let file = BufWriter::new(File::create(path.as_ref())?);
+        if is_json(path.as_ref()) {
+            serde_json::to_writer_pretty(file, self)?;
+        } else {
+            ron::ser::to_writer_pretty(file, self, ron::ser::PrettyConfig::default())?;
+        }
+        Ok(())
+    }
+
+    fn from_bytes(bytes: &[u8], json: bool) -> anyhow::Result<Self> {
+        let pose: HandPose = if json {
+            serde_json::from_slice(bytes)?
+        } else {
+            ron::de::from_bytes(bytes)?
+        };
+        if pose.rotations.len() == HandBone::ALL.len() {
+            Ok(pose)
+        } else {
+            Err(anyhow::anyhow!(
+                "a hand pose needs {} joint rotations, found {}",
+                HandBone::ALL.len(),
+                pose.rotations.len()
+            ))
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "json")
+}
+
+#[derive(Default)]
+pub struct HandPoseLoader;
+
+impl AssetLoader for HandPoseLoader {
+    type Asset = HandPose;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<HandPose, anyhow::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            HandPose::from_bytes(&bytes, is_json(load_context.path()))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["handpose.ron", "handpose.json"]
+    }
+}
+
+/// a weighted mix of hand poses, emulated hands show it instead of curling with the controller
+/// input while it is on their controller entity
+///
+/// poses that haven't loaded yet are left out of the mix
+#[derive(Component, Clone, Debug, Default)]
+pub struct HandPoseBlend {
+    pub poses: Vec<Handle<HandPose>>,
+    pub weights: Vec<f32>,
+}
+
+impl HandPoseBlend {
+    pub fn new(pose: Handle<HandPose>) -> Self {
+        Self {
+            poses: vec![pose],
+            weights: vec![1.0],
+        }
+    }
+
+    pub fn with_pose(mut self, pose: Handle<HandPose>, weight: f32) -> Self {
+        self.poses.push(pose);
+        self.weights.push(weight);
+        self
+    }
+
+    /// blends the loaded poses for `hand` by their normalized weights
+    pub fn blend(&self, hand: Hand, poses: &Assets<HandPose>) -> Option<HandPose> {
+        let mut blended: Option<HandPose> = None;
+        let mut total_weight = 0.0;
+        for (handle, weight) in self.poses.iter().zip(self.weights.iter().copied()) {
+            let pose = match poses.get(handle) {
+                Some(pose) if weight > 0.0 => pose.for_hand(hand),
+                _ => continue,
+            };
+            total_weight += weight;
+            blended = Some(match blended {
+                Some(blended) => blended.lerp(&pose, weight / total_weight),
+                None => pose,
+            });
+        }
+        blended
+    }
+}
+
+/// writes the current pose of a tracked hand to `path`
+#[derive(Event, Clone, Debug)]
+pub struct CaptureHandPose {
+    pub hand: Hand,
+    pub path: PathBuf,
+}
+
+pub fn capture_hand_poses(
+    mut events: EventReader<CaptureHandPose>,
+    hands: Option<Res<HandsResource>>,
+    active_sources: Option<Res<ActiveHandInputSources>>,
+    hand_bone_query: Query<&Transform, With<HandBone>>,
+) {
+    for event in events.read() {
+        let hands = match &hands {
+            Some(hands) => hands,
+            None => {
+                warn!("no hands to capture a pose from");
+                continue;
+            }
+        };
+        let tracked = active_sources.as_ref().map_or(false, |sources| {
+            sources.get(event.hand) == HandInputSource::OpenXr
+        });
+        if !tracked {
+            warn!(
+                "the {:?} hand isn't tracked, not capturing its pose",
+                event.hand
+            );
+            continue;
+        }
+        let hand = hands.get(event.hand);
+        let pose = HandPose::from_joint_rotations(event.hand, |bone| {
+            hand_bone_query
+                .get(hand.entity(bone))
+                .map(|transform| transform.rotation)
+                .unwrap_or_default()
+        });
+        match pose.save(&event.path) {
+            Ok(_) => info!("saved the {:?} hand pose to {:?}", event.hand, event.path),
+            Err(err) => warn!("failed to save the hand pose to {:?}: {}", event.path, err),
+        }
+    }
+}
 
 pub fn get_simulated_open_hand_transforms(hand: Hand) -> [Transform; 26] {
     let test_hand_bones: [Vec3; 26] = [
@@ -518,3 +769,62 @@ pub fn get_test_hand_pose_array() -> [Posef; 26] {
     ];
     return test_hand_pose;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curled() -> HandPose {
+        let mut pose = HandPose::open(Hand::Left);
+        for bone in HandBone::ALL {
+            let i = bone as usize as f32;
+            pose.set_rotation(
+                bone,
+                Quat::from_rotation_x(i * 0.1)
+                    * Quat::from_rotation_y(0.2)
+                    * Quat::from_rotation_z(0.3),
+            );
+        }
+        pose
+    }
+
+    fn assert_same_rotations(a: &HandPose, b: &HandPose) {
+        for bone in HandBone::ALL {
+            assert!(
+                a.rotation(bone).abs_diff_eq(b.rotation(bone), 1e-6),
+                "{:?} differs",
+                bone
+            );
+        }
+    }
+
+    #[test]
+    fn mirroring_twice_returns_the_pose() {
+        let pose = curled();
+        let mirrored = pose.for_hand(Hand::Right);
+        assert_eq!(mirrored.hand, Hand::Right);
+        assert_ne!(mirrored.rotations, pose.rotations);
+        let back = mirrored.for_hand(Hand::Left);
+        assert_eq!(back.hand, Hand::Left);
+        assert_same_rotations(&back, &pose);
+        //the pose is only mirrored for the other hand
+        assert_same_rotations(&pose.for_hand(Hand::Left), &pose);
+    }
+
+    #[test]
+    fn pose_round_trips_through_ron() {
+        let pose = curled();
+        let ron = ron::ser::to_string_pretty(&pose, ron::ser::PrettyConfig::default()).unwrap();
+        assert_eq!(HandPose::from_bytes(ron.as_bytes(), false).unwrap(), pose);
+        let json = serde_json::to_vec(&pose).unwrap();
+        assert_eq!(HandPose::from_bytes(&json, true).unwrap(), pose);
+    }
+
+    #[test]
+    fn pose_without_every_joint_is_rejected() {
+        let mut pose = HandPose::open(Hand::Right);
+        pose.rotations.pop();
+        let ron = ron::ser::to_string(&pose).unwrap();
+        assert!(HandPose::from_bytes(ron.as_bytes(), false).is_err());
+    }
+}
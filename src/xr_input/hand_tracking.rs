@@ -0,0 +1,671 @@
+use std::{ffi::c_void, ptr};
+
+use bevy::prelude::*;
+use openxr::{sys, SpaceLocationFlags, SpaceVelocityFlags};
+
+use crate::{
+    input::XrInput,
+    resources::{XrFrameState, XrInstance, XrSession},
+};
+
+use super::{
+    controllers::{XrControllerType, XrInteractionProfile},
+    gestures::HandPinchSettings,
+    hand::{ActiveHandInputSources, HandBone, HandBoneRadius, HandInputSource, HandsResource},
+    trackers::{
+        AimPose, OpenXRController, OpenXRHMD, OpenXRLeftController, OpenXRRightController,
+        XrVelocity,
+    },
+    Hand, QuatConv, Vec3Conv,
+};
+
+/// `XR_EXT_hand_tracking_data_source` is newer than the bindings, so its types are declared here
+pub const HAND_TRACKING_DATA_SOURCE_EXTENSION: &str = "XR_EXT_hand_tracking_data_source";
+const TYPE_HAND_TRACKING_DATA_SOURCE_INFO_EXT: i32 = 1000428000;
+const TYPE_HAND_TRACKING_DATA_SOURCE_STATE_EXT: i32 = 1000428001;
+const HAND_TRACKING_DATA_SOURCE_UNOBSTRUCTED_EXT: i32 = 1;
+const HAND_TRACKING_DATA_SOURCE_CONTROLLER_EXT: i32 = 2;
+
+#[repr(C)]
+struct HandTrackingDataSourceInfoEXT {
+    ty: sys::StructureType,
+    next: *const c_void,
+    requested_data_source_count: u32,
+    requested_data_sources: *mut i32,
+}
+
+#[repr(C)]
+struct HandTrackingDataSourceStateEXT {
+    ty: sys::StructureType,
+    next: *mut c_void,
+    is_active: sys::Bool32,
+    data_source: i32,
+}
+
+/// an `XR_EXT_hand_tracking` tracker, created by hand so the requested data sources can be chained
+/// onto it
+pub struct XrHandTracker {
+    handle: sys::HandTrackerEXT,
+    destroy: sys::pfn::DestroyHandTrackerEXT,
+    //the tracker must be destroyed before its session
+    _session: openxr::Session<openxr::AnyGraphics>,
+}
+
+impl XrHandTracker {
+    /// creates the tracker of `hand`, with `data_source_supported` it asks for the joints of
+    /// held controllers too, otherwise the runtime only reports optically tracked hands
+    pub fn new(
+        instance: &XrInstance,
+        session: &XrSession,
+        hand: Hand,
+        data_source_supported: bool,
+    ) -> openxr::Result<Self> {
+        let ext = instance
+            .exts()
+            .ext_hand_tracking
+            .as_ref()
+            .ok_or(sys::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+        let mut requested_data_sources = [
+            HAND_TRACKING_DATA_SOURCE_UNOBSTRUCTED_EXT,
+            HAND_TRACKING_DATA_SOURCE_CONTROLLER_EXT,
+        ];
+        let data_source_info = HandTrackingDataSourceInfoEXT {
+            ty: sys::StructureType::from_raw(TYPE_HAND_TRACKING_DATA_SOURCE_INFO_EXT),
+            next: ptr::null(),
+            requested_data_source_count: requested_data_sources.len() as u32,
+            requested_data_sources: requested_data_sources.as_mut_ptr(),
+        };
+        let info = sys::HandTrackerCreateInfoEXT {
+            ty: sys::HandTrackerCreateInfoEXT::TYPE,
+            next: if data_source_supported {
+                &data_source_info as *const _ as *const _
+            } else {
+                ptr::null()
+            },
+            hand: match hand {
+                Hand::Left => sys::HandEXT::LEFT,
+                Hand::Right => sys::HandEXT::RIGHT,
+            },
+            hand_joint_set: sys::HandJointSetEXT::DEFAULT,
+        };
+        let mut handle = sys::HandTrackerEXT::NULL;
+        let result = unsafe { (ext.create_hand_tracker)(session.as_raw(), &info, &mut handle) };
+        if result.into_raw() < 0 {
+            return Err(result);
+        }
+        Ok(Self {
+            handle,
+            destroy: ext.destroy_hand_tracker,
+            _session: (**session).clone(),
+        })
+    }
+
+    pub fn as_raw(&self) -> sys::HandTrackerEXT {
+        self.handle
+    }
+}
+
+impl Drop for XrHandTracker {
+    fn drop(&mut self) {
+        unsafe {
+            (self.destroy)(self.handle);
+        }
+    }
+}
+
+/// the `XR_EXT_hand_tracking` trackers, only inserted when the runtime supports hand tracking
+#[derive(Resource)]
+pub struct HandTrackers {
+    pub left: XrHandTracker,
+    pub right: XrHandTracker,
+    /// whether the runtime reports where the joints come from
+    pub data_source_supported: bool,
+}
+
+impl HandTrackers {
+    pub fn get(&self, hand: Hand) -> &XrHandTracker {
+        match hand {
+            Hand::Left => &self.left,
+            Hand::Right => &self.right,
+        }
+    }
+}
+
+/// how the joints are located while the hand holds a controller
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandMotionRange {
+    /// the joints follow the hand as closely as possible, fingers can go through the controller
+    Unobstructed,
+    /// the joints wrap around the controller
+    ConformingToController,
+}
+
+impl Default for HandMotionRange {
+    fn default() -> Self {
+        HandMotionRange::Unobstructed
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct HandTrackingSettings {
+    /// only used with `XR_EXT_hand_joints_motion_range`
+    pub motion_range: HandMotionRange,
+}
+
+/// where the joints of a hand come from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandDataSource {
+    /// tracked by the runtime's cameras
+    Optical,
+    /// derived by the runtime from a held controller
+    Controller,
+    /// emulated by us from the controller input
+    Emulated,
+}
+
+impl Default for HandDataSource {
+    fn default() -> Self {
+        HandDataSource::Emulated
+    }
+}
+
+/// the data source of each hand, updated together with the `HandBone` entities
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct HandDataSources {
+    pub left: HandDataSource,
+    pub right: HandDataSource,
+}
+
+impl HandDataSources {
+    pub fn get(&self, hand: Hand) -> HandDataSource {
+        match hand {
+            Hand::Left => self.left,
+            Hand::Right => self.right,
+        }
+    }
+
+    pub fn get_mut(&mut self, hand: Hand) -> &mut HandDataSource {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
+    }
+}
+
+/// the located joints of a tracked hand, in tracking root space
+#[derive(Clone, Copy, Debug)]
+pub struct HandJoints {
+    pub locations: [sys::HandJointLocationEXT; HandBone::ALL.len()],
+    pub velocities: [sys::HandJointVelocityEXT; HandBone::ALL.len()],
+    /// `None` without `XR_FB_hand_tracking_aim` or while the runtime's aim isn't valid
+    pub aim: Option<HandAimState>,
+    /// `None` without `XR_EXT_hand_tracking_data_source`
+    pub data_source: Option<HandDataSource>,
+}
+
+/// the joints of each hand located this frame, `None` while the hand isn't tracked
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct TrackedHandJoints {
+    pub left: Option<HandJoints>,
+    pub right: Option<HandJoints>,
+}
+
+impl TrackedHandJoints {
+    pub fn get(&self, hand: Hand) -> Option<&HandJoints> {
+        match hand {
+            Hand::Left => self.left.as_ref(),
+            Hand::Right => self.right.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, hand: Hand) -> &mut Option<HandJoints> {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
+    }
+}
+
+pub fn hand_tracking_supported(instance: &XrInstance) -> bool {
+    instance.exts().ext_hand_tracking.is_some()
+}
+
+pub fn hand_tracking_aim_supported(instance: &XrInstance) -> bool {
+    instance.exts().fb_hand_tracking_aim.is_some()
+}
+
+pub fn hand_joints_motion_range_supported(instance: &XrInstance) -> bool {
+    instance.exts().ext_hand_joints_motion_range.is_some()
+}
+
+pub fn hand_tracking_data_source_supported(instance: &XrInstance) -> bool {
+    instance
+        .entry()
+        .enumerate_extensions()
+        .map(|extensions| {
+            extensions
+                .other
+                .iter()
+                .any(|extension| extension == HAND_TRACKING_DATA_SOURCE_EXTENSION)
+        })
+        .unwrap_or(false)
+}
+
+pub fn setup_hand_trackers(
+    mut commands: Commands,
+    instance: Option<Res<XrInstance>>,
+    session: Option<Res<XrSession>>,
+) {
+    let (instance, session) = match (instance, session) {
+        (Some(instance), Some(session)) => (instance, session),
+        _ => return,
+    };
+    if !hand_tracking_supported(&instance) {
+        info!("XR_EXT_hand_tracking is not supported, hands can only be emulated");
+        return;
+    }
+    let data_source_supported = hand_tracking_data_source_supported(&instance);
+    match (
+        XrHandTracker::new(&instance, &session, Hand::Left, data_source_supported),
+        XrHandTracker::new(&instance, &session, Hand::Right, data_source_supported),
+    ) {
+        (Ok(left), Ok(right)) => commands.insert_resource(HandTrackers {
+            left,
+            right,
+            data_source_supported,
+        }),
+        (Err(err), _) | (_, Err(err)) => warn!("failed to create the hand trackers: {}", err),
+    }
+}
+
+/// locates the joints of `hand` at the predicted display time, with their velocities and whatever
+/// the enabled extensions chain on, `None` while the hand isn't tracked
+pub fn locate_hand_joints(
+    instance: &XrInstance,
+    trackers: &HandTrackers,
+    xr_input: &XrInput,
+    frame_state: &XrFrameState,
+    settings: &HandTrackingSettings,
+    hand: Hand,
+) -> Option<HandJoints> {
+    let ext = instance.exts().ext_hand_tracking.as_ref()?;
+    let frame_state = *frame_state.lock().unwrap();
+    let motion_range = sys::HandJointsMotionRangeInfoEXT {
+        ty: sys::HandJointsMotionRangeInfoEXT::TYPE,
+        next: ptr::null(),
+        hand_joints_motion_range: match settings.motion_range {
+            HandMotionRange::Unobstructed => sys::HandJointsMotionRangeEXT::UNOBSTRUCTED,
+            HandMotionRange::ConformingToController => {
+                sys::HandJointsMotionRangeEXT::CONFORMING_TO_CONTROLLER
+            }
+        },
+    };
+    let info = sys::HandJointsLocateInfoEXT {
+        ty: sys::HandJointsLocateInfoEXT::TYPE,
+        next: if hand_joints_motion_range_supported(instance) {
+            &motion_range as *const _ as *const _
+        } else {
+            ptr::null()
+        },
+        base_space: xr_input.stage.as_raw(),
+        time: frame_state.predicted_display_time,
+    };
+    //the optional outputs are chained from the back
+    let mut data_source = HandTrackingDataSourceStateEXT {
+        ty: sys::StructureType::from_raw(TYPE_HAND_TRACKING_DATA_SOURCE_STATE_EXT),
+        next: ptr::null_mut(),
+        is_active: sys::FALSE,
+        data_source: 0,
+    };
+    let mut aim_state = sys::HandTrackingAimStateFB {
+        ty: sys::HandTrackingAimStateFB::TYPE,
+        next: ptr::null_mut(),
+        status: sys::HandTrackingAimFlagsFB::EMPTY,
+        aim_pose: openxr::Posef::IDENTITY,
+        pinch_strength_index: 0.0,
+        pinch_strength_middle: 0.0,
+        pinch_strength_ring: 0.0,
+        pinch_strength_little: 0.0,
+    };
+    let mut next: *mut c_void = ptr::null_mut();
+    if trackers.data_source_supported {
+        next = &mut data_source as *mut _ as *mut _;
+    }
+    if hand_tracking_aim_supported(instance) {
+        aim_state.next = next;
+        next = &mut aim_state as *mut _ as *mut _;
+    }
+    let mut velocities = [sys::HandJointVelocityEXT {
+        velocity_flags: SpaceVelocityFlags::EMPTY,
+        linear_velocity: sys::Vector3f::default(),
+        angular_velocity: sys::Vector3f::default(),
+    }; HandBone::ALL.len()];
+    let mut joint_velocities = sys::HandJointVelocitiesEXT {
+        ty: sys::HandJointVelocitiesEXT::TYPE,
+        next,
+        joint_count: velocities.len() as u32,
+        joint_velocities: velocities.as_mut_ptr(),
+    };
+    let mut joints = [sys::HandJointLocationEXT {
+        location_flags: SpaceLocationFlags::EMPTY,
+        pose: openxr::Posef::IDENTITY,
+        radius: 0.0,
+    }; HandBone::ALL.len()];
+    let mut locations = sys::HandJointLocationsEXT {
+        ty: sys::HandJointLocationsEXT::TYPE,
+        next: &mut joint_velocities as *mut _ as *mut _,
+        is_active: sys::FALSE,
+        joint_count: joints.len() as u32,
+        joint_locations: joints.as_mut_ptr(),
+    };
+    let result =
+        unsafe { (ext.locate_hand_joints)(trackers.get(hand).as_raw(), &info, &mut locations) };
+    if result.into_raw() < 0 {
+        warn!("failed to locate the hand joints: {}", result);
+        return None;
+    }
+    if locations.is_active == sys::FALSE {
+        return None;
+    }
+    Some(HandJoints {
+        locations: joints,
+        velocities,
+        aim: if hand_tracking_aim_supported(instance) {
+            runtime_hand_aim(&aim_state)
+        } else {
+            None
+        },
+        data_source: if trackers.data_source_supported {
+            hand_data_source(data_source.is_active, data_source.data_source)
+        } else {
+            None
+        },
+    })
+}
+
+/// maps a `XrHandTrackingDataSourceStateEXT` onto where the joints come from, `None` while the
+/// runtime has no data source active
+fn hand_data_source(is_active: sys::Bool32, data_source: i32) -> Option<HandDataSource> {
+    if is_active == sys::FALSE {
+        return None;
+    }
+    match data_source {
+        HAND_TRACKING_DATA_SOURCE_CONTROLLER_EXT => Some(HandDataSource::Controller),
+        _ => Some(HandDataSource::Optical),
+    }
+}
+
+/// locates both hands once per frame into `TrackedHandJoints`
+pub fn locate_tracked_hands(
+    settings: Res<HandTrackingSettings>,
+    mut tracked_joints: ResMut<TrackedHandJoints>,
+    trackers: Option<Res<HandTrackers>>,
+    instance: Option<Res<XrInstance>>,
+    xr_input: Option<Res<XrInput>>,
+    frame_state: Option<Res<XrFrameState>>,
+) {
+    for hand in [Hand::Left, Hand::Right] {
+        *tracked_joints.get_mut(hand) = match (&instance, &trackers, &xr_input, &frame_state) {
+            (Some(instance), Some(trackers), Some(xr_input), Some(frame_state)) => {
+                locate_hand_joints(instance, trackers, xr_input, frame_state, &settings, hand)
+            }
+            _ => None,
+        };
+    }
+}
+
+/// writes the located joints into the `HandBone` entities of `hand`, invalid joints keep their
+/// last pose
+pub fn update_hand_bones_openxr(
+    hand: Hand,
+    joints: &HandJoints,
+    hands: &HandsResource,
+    hand_bone_query: &mut Query<(&mut Transform, &HandBone, &Hand)>,
+    radius_query: &mut Query<&mut HandBoneRadius>,
+    velocity_query: &mut Query<&mut XrVelocity, With<HandBone>>,
+) {
+    for bone in HandBone::ALL {
+        let entity = hands.get(hand).entity(bone);
+        let location = &joints.locations[bone.joint()];
+        if let Ok((mut transform, ..)) = hand_bone_query.get_mut(entity) {
+            if location
+                .location_flags
+                .contains(SpaceLocationFlags::POSITION_VALID)
+            {
+                transform.translation = location.pose.position.to_vec3();
+            }
+            if location
+                .location_flags
+                .contains(SpaceLocationFlags::ORIENTATION_VALID)
+            {
+                transform.rotation = location.pose.orientation.to_quat();
+            }
+        }
+        if let Ok(mut radius) = radius_query.get_mut(entity) {
+            radius.0 = location.radius;
+        }
+        if let Ok(mut velocity) = velocity_query.get_mut(entity) {
+            *velocity = XrVelocity::from(joints.velocities[bone.joint()]);
+        }
+    }
+}
+
+/// the aim ray and pinch state of a tracked hand
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HandAimState {
+    /// the aim ray in tracking root space, it points along -Z like `AimPose`
+    pub aim: Transform,
+    /// how close the thumb is to each finger, from 0.0 apart to 1.0 touching
+    pub pinch_strength_index: f32,
+    pub pinch_strength_middle: f32,
+    pub pinch_strength_ring: f32,
+    pub pinch_strength_little: f32,
+    pub index_pinching: bool,
+    /// the hand does the runtime's system gesture, usually a pinch with the palm facing the user
+    pub system_gesture: bool,
+    /// the system gesture of the non dominant hand was completed, apps should open their menu
+    pub menu_pressed: bool,
+    pub dominant_hand: bool,
+    /// whether the aim comes from the runtime or is estimated by us
+    pub from_runtime: bool,
+}
+
+/// the aim of each hand while its joints are tracked
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct HandAimStates {
+    pub left: Option<HandAimState>,
+    pub right: Option<HandAimState>,
+}
+
+impl HandAimStates {
+    pub fn get(&self, hand: Hand) -> Option<&HandAimState> {
+        match hand {
+            Hand::Left => self.left.as_ref(),
+            Hand::Right => self.right.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, hand: Hand) -> &mut Option<HandAimState> {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
+    }
+}
+
+/// how the aim is estimated without `XR_FB_hand_tracking_aim`
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HandAimSettings {
+    /// the right shoulder relative to the head, ignoring head pitch and roll, the left one is
+    /// mirrored
+    pub shoulder_offset: Vec3,
+}
+
+impl Default for HandAimSettings {
+    fn default() -> Self {
+        Self {
+            shoulder_offset: Vec3::new(0.15, -0.2, 0.05),
+        }
+    }
+}
+
+/// the aim chained onto a joint locate, `None` while the runtime's aim isn't valid
+fn runtime_hand_aim(aim_state: &sys::HandTrackingAimStateFB) -> Option<HandAimState> {
+    let status = aim_state.status;
+    if !status.contains(sys::HandTrackingAimFlagsFB::VALID) {
+        return None;
+    }
+    Some(HandAimState {
+        aim: Transform {
+            translation: aim_state.aim_pose.position.to_vec3(),
+            rotation: aim_state.aim_pose.orientation.to_quat(),
+            ..default()
+        },
+        pinch_strength_index: aim_state.pinch_strength_index,
+        pinch_strength_middle: aim_state.pinch_strength_middle,
+        pinch_strength_ring: aim_state.pinch_strength_ring,
+        pinch_strength_little: aim_state.pinch_strength_little,
+        index_pinching: status.contains(sys::HandTrackingAimFlagsFB::INDEX_PINCHING),
+        system_gesture: status.contains(sys::HandTrackingAimFlagsFB::SYSTEM_GESTURE),
+        menu_pressed: status.contains(sys::HandTrackingAimFlagsFB::MENU_PRESSED),
+        dominant_hand: status.contains(sys::HandTrackingAimFlagsFB::DOMINANT_HAND),
+        from_runtime: true,
+    })
+}
+
+/// estimates the aim from the hand bones, the ray goes from a shoulder estimate through the index
+/// knuckle, `was_pinching` is the previous `index_pinching`
+pub fn estimate_hand_aim(
+    hand: Hand,
+    head: &Transform,
+    settings: &HandAimSettings,
+    pinch: &HandPinchSettings,
+    was_pinching: bool,
+    joint: impl Fn(HandBone) -> Vec3,
+) -> HandAimState {
+    let shoulder_offset = match hand {
+        Hand::Left => settings.shoulder_offset * Vec3::new(-1.0, 1.0, 1.0),
+        Hand::Right => settings.shoulder_offset,
+    };
+    //only the head yaw moves the shoulders
+    let forward = head.forward();
+    let yaw = Quat::from_rotation_y(f32::atan2(-forward.x, -forward.z));
+    let shoulder = head.translation + yaw * shoulder_offset;
+    let knuckle = joint(HandBone::IndexProximal);
+    let direction = (knuckle - shoulder).normalize_or_zero();
+    let pinch_strength =
+        |finger: HandBone| pinch.strength(joint(HandBone::ThumbTip), joint(finger));
+    let pinch_strength_index = pinch_strength(HandBone::IndexTip);
+    HandAimState {
+        aim: Transform::from_translation(knuckle).looking_to(direction, Vec3::Y),
+        pinch_strength_index,
+        pinch_strength_middle: pinch_strength(HandBone::MiddleTip),
+        pinch_strength_ring: pinch_strength(HandBone::RingTip),
+        pinch_strength_little: pinch_strength(HandBone::LittleTip),
+        index_pinching: pinch
+            .threshold
+            .is_active(pinch_strength_index, was_pinching),
+        ..default()
+    }
+}
+
+/// fills `HandAimStates` and the `AimPose` of the palm entities for the tracked hands
+///
+/// the runtime's aim is used when it has one, either from `XR_FB_hand_tracking_aim` or from the
+/// controller aim of the hand interaction profile, otherwise it is estimated from the joints
+pub fn update_hand_aim(
+    settings: Res<HandAimSettings>,
+    pinch: Res<HandPinchSettings>,
+    mut aim_states: ResMut<HandAimStates>,
+    active_sources: Res<ActiveHandInputSources>,
+    tracked_joints: Res<TrackedHandJoints>,
+    interaction_profile: Option<Res<XrInteractionProfile>>,
+    hands: Option<Res<HandsResource>>,
+    head_query: Query<&Transform, With<OpenXRHMD>>,
+    hand_bone_query: Query<&Transform, With<HandBone>>,
+    controller_query: Query<
+        (
+            &AimPose,
+            Has<OpenXRLeftController>,
+            Has<OpenXRRightController>,
+        ),
+        (With<OpenXRController>, Without<HandBone>),
+    >,
+    mut aim_query: Query<(&mut AimPose, &Hand), With<HandBone>>,
+) {
+    for hand in [Hand::Left, Hand::Right] {
+        //the hand interaction profile locates the aim of tracked hands on the controller entities
+        let controller_type = interaction_profile
+            .as_ref()
+            .and_then(|profile| profile.controller_type(hand));
+        let controller_aim = match controller_type {
+            Some(XrControllerType::HandInteraction) => controller_query
+                .iter()
+                .find(|(_, is_left, is_right)| match hand {
+                    Hand::Left => *is_left,
+                    Hand::Right => *is_right,
+                })
+                .map(|(aim, _, _)| aim.0),
+            _ => None,
+        };
+        let state = match (active_sources.get(hand), &hands) {
+            (HandInputSource::OpenXr, Some(hands)) => tracked_joints
+                .get(hand)
+                .and_then(|joints| joints.aim)
+                .or_else(|| {
+                    let head = head_query.get_single().ok()?;
+                    let hand_resource = hands.get(hand);
+                    let was_pinching = aim_states
+                        .get(hand)
+                        .map(|state| state.index_pinching)
+                        .unwrap_or(false);
+                    let estimate =
+                        estimate_hand_aim(hand, head, &settings, &pinch, was_pinching, |bone| {
+                            hand_bone_query
+                                .get(hand_resource.entity(bone))
+                                .map(|transform| transform.translation)
+                                .unwrap_or_default()
+                        });
+                    //the pinch strengths are still estimated, the profile only has the aim
+                    Some(match controller_aim {
+                        Some(aim) => HandAimState {
+                            aim,
+                            from_runtime: true,
+                            ..estimate
+                        },
+                        None => estimate,
+                    })
+                }),
+            _ => None,
+        };
+        *aim_states.get_mut(hand) = state;
+        if let Some(state) = state {
+            for (mut aim, aim_hand) in aim_query.iter_mut() {
+                if *aim_hand == hand {
+                    aim.0 = state.aim;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_source_maps_onto_where_the_joints_come_from() {
+        assert_eq!(
+            hand_data_source(sys::TRUE, HAND_TRACKING_DATA_SOURCE_UNOBSTRUCTED_EXT),
+            Some(HandDataSource::Optical)
+        );
+        assert_eq!(
+            hand_data_source(sys::TRUE, HAND_TRACKING_DATA_SOURCE_CONTROLLER_EXT),
+            Some(HandDataSource::Controller)
+        );
+        assert_eq!(
+            hand_data_source(sys::FALSE, HAND_TRACKING_DATA_SOURCE_CONTROLLER_EXT),
+            None
+        );
+    }
+}
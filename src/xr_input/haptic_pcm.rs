@@ -0,0 +1,400 @@
+use std::collections::VecDeque;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::audio::{Decodable, Sample, Source};
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use openxr::sys;
+
+use crate::resources::{XrInstance, XrSession};
+
+use super::{
+    haptics::{HapticPattern, XrHapticEvent, XrHapticPatternEvent},
+    oculus_touch::{subaction_path, OculusController},
+    Hand,
+};
+
+/// the most samples `XR_FB_haptic_pcm` accepts in a single call
+const MAX_PCM_BUFFER_SIZE: usize = 4000;
+/// the window used to turn pcm samples into an amplitude envelope when pcm isn't supported
+const ENVELOPE_STEP: Duration = Duration::from_millis(20);
+
+/// plays a buffer of pcm samples on a controller, replacing whatever was playing on that hand
+///
+/// runtimes with `XR_FB_haptic_pcm` get the samples streamed to them, everywhere else the samples
+/// are turned into an amplitude envelope that is played as a [`HapticPattern`]
+#[derive(Clone, Debug, PartialEq, Event)]
+pub struct XrHapticPcmEvent {
+    pub hand: Hand,
+    /// mono samples from -1.0 to 1.0
+    pub samples: Vec<f32>,
+    pub sample_rate: f32,
+}
+
+impl XrHapticPcmEvent {
+    pub fn new(hand: Hand, samples: Vec<f32>, sample_rate: f32) -> Self {
+        Self {
+            hand,
+            samples,
+            sample_rate,
+        }
+    }
+
+    /// decodes an audio clip and mixes it down to mono, this blocks until the whole clip is
+    /// decoded, send an [`XrHapticAudioEvent`] to decode it in the background instead
+    ///
+    /// ```ignore
+    /// fn buzz(sources: Res<Assets<AudioSource>>, mut pcm: EventWriter<XrHapticPcmEvent>) {
+    ///     if let Some(source) = sources.get(&beat) {
+    ///         pcm.send(XrHapticPcmEvent::from_audio_source(Hand::Right, source));
+    ///     }
+    /// }
+    /// ```
+    pub fn from_audio_source(hand: Hand, source: &AudioSource) -> Self {
+        let decoder = source.decoder();
+        let channels = decoder.channels().max(1) as usize;
+        let sample_rate = decoder.sample_rate() as f32;
+        let interleaved = decoder.map(|sample| sample.to_f32()).collect::<Vec<_>>();
+        let samples = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect();
+        Self::new(hand, samples, sample_rate)
+    }
+
+    pub fn duration(&self) -> Duration {
+        if self.sample_rate > 0.0 {
+            Duration::from_secs_f32(self.samples.len() as f32 / self.sample_rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// the rms amplitude of every `step` long window, used when pcm isn't supported
+    pub fn envelope(&self, step: Duration) -> HapticPattern {
+        envelope(&self.samples, self.sample_rate, step)
+    }
+}
+
+/// plays an audio clip on a controller like an [`XrHapticPcmEvent`], the clip is decoded on the
+/// async compute pool and starts playing once it is decoded, clips that aren't loaded are skipped
+#[derive(Clone, Debug, PartialEq, Event)]
+pub struct XrHapticAudioEvent {
+    pub hand: Hand,
+    pub source: Handle<AudioSource>,
+}
+
+impl XrHapticAudioEvent {
+    pub fn new(hand: Hand, source: Handle<AudioSource>) -> Self {
+        Self { hand, source }
+    }
+}
+
+/// clips decoded in the background, sent as `XrHapticPcmEvent`s on the next update
+#[derive(Resource, Default)]
+pub struct XrHapticAudioDecodes {
+    decoded: Arc<Mutex<Vec<XrHapticPcmEvent>>>,
+}
+
+pub fn decode_haptic_audio(
+    mut events: EventReader<XrHapticAudioEvent>,
+    mut pcm: EventWriter<XrHapticPcmEvent>,
+    decodes: Res<XrHapticAudioDecodes>,
+    sources: Option<Res<Assets<AudioSource>>>,
+) {
+    for event in events.read() {
+        let source = match sources
+            .as_ref()
+            .and_then(|sources| sources.get(&event.source))
+        {
+            Some(source) => source.clone(),
+            None => {
+                warn!("haptic audio clip {:?} isn't loaded", event.source);
+                continue;
+            }
+        };
+        let hand = event.hand;
+        let decoded = decodes.decoded.clone();
+        AsyncComputeTaskPool::get()
+            .spawn(async move {
+                let event = XrHapticPcmEvent::from_audio_source(hand, &source);
+                decoded.lock().unwrap().push(event);
+            })
+            .detach();
+    }
+    for event in decodes.decoded.lock().unwrap().drain(..) {
+        pcm.send(event);
+    }
+}
+
+fn envelope(samples: &[f32], sample_rate: f32, step: Duration) -> HapticPattern {
+    let window = ((sample_rate * step.as_secs_f32()) as usize).max(1);
+    samples
+        .chunks(window)
+        .fold(HapticPattern::new(), |pattern, chunk| {
+            let rms = (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt();
+            pattern.pulse(rms.clamp(0.0, 1.0), step)
+        })
+}
+
+/// samples waiting to be handed to the runtime
+#[derive(Clone, Debug, Default)]
+pub struct HapticPcmStream {
+    pub samples: VecDeque<f32>,
+    pub sample_rate: f32,
+    /// only the first buffer of a clip replaces what is playing, the rest is appended
+    pub append: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct XrHapticPcmStreams {
+    pub left: HapticPcmStream,
+    pub right: HapticPcmStream,
+}
+
+impl XrHapticPcmStreams {
+    pub fn get_mut(&mut self, hand: Hand) -> &mut HapticPcmStream {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
+    }
+}
+
+pub fn pcm_supported(instance: &XrInstance) -> bool {
+    instance.exts().fb_haptic_pcm.is_some()
+}
+
+/// queues pcm events on runtimes with `XR_FB_haptic_pcm` and turns them into envelopes elsewhere
+pub fn queue_haptic_pcm(
+    mut events: EventReader<XrHapticPcmEvent>,
+    mut streams: ResMut<XrHapticPcmStreams>,
+    mut patterns: EventWriter<XrHapticPatternEvent>,
+    instance: Option<Res<XrInstance>>,
+    session: Option<Res<XrSession>>,
+    oculus_controller: Option<Res<OculusController>>,
+) {
+    let device = match (instance, session, oculus_controller) {
+        (Some(instance), Some(session), Some(controller)) if pcm_supported(&instance) => {
+            Some((instance, session, controller))
+        }
+        _ => None,
+    };
+    for event in events.read() {
+        let (instance, session, controller) = match &device {
+            Some(device) => device,
+            None => {
+                patterns.send(XrHapticPatternEvent::new(
+                    event.hand,
+                    event.envelope(ENVELOPE_STEP),
+                ));
+                continue;
+            }
+        };
+        //play the clip at the device rate, haptic motors often can't play the clip rate
+        let sample_rate = device_sample_rate(instance, session, controller, event.hand)
+            .unwrap_or(event.sample_rate);
+        let stream = streams.get_mut(event.hand);
+        stream.samples = resample(&event.samples, event.sample_rate, sample_rate).into();
+        stream.sample_rate = sample_rate;
+        stream.append = false;
+    }
+}
+
+/// drops the queued samples of a hand when its vibration is stopped, stopping the controller only
+/// stops what the runtime already has
+pub fn stop_haptic_pcm(
+    mut events: EventReader<XrHapticEvent>,
+    mut streams: ResMut<XrHapticPcmStreams>,
+) {
+    for event in events.read() {
+        if event.is_stop() {
+            streams.get_mut(event.hand).samples.clear();
+        }
+    }
+}
+
+/// hands the queued samples to the runtime, the runtime reports how many it took and the rest is
+/// retried next frame, when the runtime rejects them the rest is played as an envelope
+pub fn stream_haptic_pcm(
+    mut streams: ResMut<XrHapticPcmStreams>,
+    mut patterns: EventWriter<XrHapticPatternEvent>,
+    instance: Res<XrInstance>,
+    session: Res<XrSession>,
+    oculus_controller: Res<OculusController>,
+) {
+    if !pcm_supported(&instance) {
+        return;
+    }
+    for hand in [Hand::Left, Hand::Right] {
+        let stream = streams.get_mut(hand);
+        if stream.samples.is_empty() {
+            continue;
+        }
+        let len = stream.samples.len().min(MAX_PCM_BUFFER_SIZE);
+        let buffer = stream.samples.range(..len).copied().collect::<Vec<_>>();
+        let mut samples_consumed = 0;
+        let vibration = sys::HapticPcmVibrationFB {
+            ty: sys::HapticPcmVibrationFB::TYPE,
+            next: ptr::null(),
+            buffer_size: buffer.len() as u32,
+            buffer: buffer.as_ptr(),
+            sample_rate: stream.sample_rate,
+            append: stream.append.into(),
+            samples_consumed: &mut samples_consumed,
+        };
+        let info = haptic_action_info(&oculus_controller, hand);
+        let result = unsafe {
+            (instance.fp().apply_haptic_feedback)(
+                session.as_raw(),
+                &info,
+                &vibration as *const _ as *const sys::HapticBaseHeader,
+            )
+        };
+        if result.into_raw() < 0 {
+            warn!("failed to stream haptic pcm samples: {}", result);
+            let samples = stream.samples.drain(..).collect::<Vec<_>>();
+            patterns.send(XrHapticPatternEvent::new(
+                hand,
+                envelope(&samples, stream.sample_rate, ENVELOPE_STEP),
+            ));
+            continue;
+        }
+        stream.samples.drain(..(samples_consumed as usize).min(len));
+        stream.append = true;
+    }
+}
+
+fn haptic_action_info(controller: &OculusController, hand: Hand) -> sys::HapticActionInfo {
+    sys::HapticActionInfo {
+        ty: sys::HapticActionInfo::TYPE,
+        next: ptr::null(),
+        action: controller.haptic_feedback.as_raw(),
+        subaction_path: subaction_path(hand),
+    }
+}
+
+fn device_sample_rate(
+    instance: &XrInstance,
+    session: &XrSession,
+    controller: &OculusController,
+    hand: Hand,
+) -> Option<f32> {
+    let ext = instance.exts().fb_haptic_pcm.as_ref()?;
+    let info = haptic_action_info(controller, hand);
+    let mut sample_rate = sys::DevicePcmSampleRateGetInfoFB {
+        ty: sys::DevicePcmSampleRateGetInfoFB::TYPE,
+        next: ptr::null_mut(),
+        sample_rate: 0.0,
+    };
+    let result = unsafe { (ext.get_device_sample_rate)(session.as_raw(), &info, &mut sample_rate) };
+    if result.into_raw() < 0 || sample_rate.sample_rate <= 0.0 {
+        None
+    } else {
+        Some(sample_rate.sample_rate)
+    }
+}
+
+/// linear resampling, good enough for haptics
+fn resample(samples: &[f32], from: f32, to: f32) -> Vec<f32> {
+    if from <= 0.0 || to <= 0.0 || from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let len = (samples.len() as f32 * to / from).round() as usize;
+    (0..len)
+        .map(|i| {
+            let position = i as f32 * from / to;
+            let index = position as usize;
+            let a = samples[index.min(samples.len() - 1)];
+            let b = samples[(index + 1).min(samples.len() - 1)];
+            a + (b - a) * position.fract()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xr_input::haptics::HapticStep;
+
+    #[test]
+    fn resample_keeps_samples_at_the_same_rate() {
+        let samples = vec![0.0, 0.5, -0.5, 1.0];
+        assert_eq!(resample(&samples, 1000.0, 1000.0), samples);
+        assert_eq!(resample(&samples, 0.0, 1000.0), samples);
+        assert!(resample(&[], 1000.0, 2000.0).is_empty());
+    }
+
+    #[test]
+    fn resample_interpolates_between_samples() {
+        let upsampled = resample(&[0.0, 1.0], 1000.0, 2000.0);
+        assert_eq!(upsampled, vec![0.0, 0.5, 1.0, 1.0]);
+        let downsampled = resample(&[0.0, 0.25, 0.5, 0.75], 2000.0, 1000.0);
+        assert_eq!(downsampled, vec![0.0, 0.5]);
+    }
+
+    #[test]
+    fn envelope_has_a_pulse_per_window() {
+        let step = Duration::from_millis(10);
+        //25ms at 1khz, the last window is half full
+        let event = XrHapticPcmEvent::new(Hand::Left, vec![0.5; 25], 1000.0);
+        let pattern = event.envelope(step);
+        assert_eq!(pattern.steps.len(), 3);
+        for step in pattern.steps {
+            match step {
+                HapticStep::Pulse { amplitude, .. } => assert!((amplitude - 0.5).abs() < 1e-6),
+                step => panic!("unexpected {:?}", step),
+            }
+        }
+    }
+
+    #[test]
+    fn stopping_a_hand_drops_its_queued_samples() {
+        let mut app = App::new();
+        app.add_event::<XrHapticEvent>()
+            .init_resource::<XrHapticPcmStreams>()
+            .add_systems(Update, stop_haptic_pcm);
+        {
+            let mut streams = app.world.resource_mut::<XrHapticPcmStreams>();
+            streams.left.samples = vec![0.5; 10].into();
+            streams.right.samples = vec![0.5; 10].into();
+        }
+        app.world.send_event(XrHapticEvent::new(
+            Hand::Left,
+            1.0,
+            Duration::from_millis(10),
+        ));
+        app.update();
+        assert_eq!(
+            app.world
+                .resource::<XrHapticPcmStreams>()
+                .left
+                .samples
+                .len(),
+            10
+        );
+        app.world.send_event(XrHapticEvent::stop(Hand::Left));
+        app.update();
+        let streams = app.world.resource::<XrHapticPcmStreams>();
+        assert!(streams.left.samples.is_empty());
+        assert_eq!(streams.right.samples.len(), 10);
+    }
+
+    #[test]
+    fn envelope_is_clamped() {
+        let pattern = envelope(&[2.0, -2.0], 1000.0, Duration::from_millis(10));
+        assert_eq!(
+            pattern.steps,
+            vec![HapticStep::Pulse {
+                amplitude: 1.0,
+                frequency: 0.0,
+                duration: Duration::from_millis(10),
+            }]
+        );
+        assert!(envelope(&[], 1000.0, Duration::from_millis(10))
+            .steps
+            .is_empty());
+    }
+}
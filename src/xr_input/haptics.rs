@@ -0,0 +1,410 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use openxr::HapticVibration;
+
+use crate::resources::XrSession;
+
+use super::{
+    haptic_pcm::{
+        decode_haptic_audio, queue_haptic_pcm, stop_haptic_pcm, stream_haptic_pcm,
+        XrHapticAudioDecodes, XrHapticAudioEvent, XrHapticPcmEvent, XrHapticPcmStreams,
+    },
+    oculus_touch::{subaction_path, OculusController},
+    Hand,
+};
+
+/// Plays [`XrHapticEvent`]s, [`XrHapticPatternEvent`]s, [`XrHapticPcmEvent`]s and
+/// [`XrHapticAudioEvent`]s on the controllers.
+///
+/// This is added by `OpenXrInput`.
+#[derive(Default)]
+pub struct XrHapticsPlugin;
+
+impl Plugin for XrHapticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<XrHapticEvent>()
+            .add_event::<XrHapticPatternEvent>()
+            .add_event::<XrHapticPcmEvent>()
+            .add_event::<XrHapticAudioEvent>()
+            .init_resource::<XrHapticPatterns>()
+            .init_resource::<XrHapticPcmStreams>()
+            .init_resource::<XrHapticAudioDecodes>()
+            .add_systems(
+                Update,
+                (
+                    decode_haptic_audio,
+                    queue_haptic_pcm,
+                    play_haptic_patterns,
+                    stop_haptic_pcm,
+                    apply_haptic_events.run_if(resource_exists::<OculusController>()),
+                    stream_haptic_pcm.run_if(resource_exists::<OculusController>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// vibrates a controller, a zero amplitude or duration stops any vibration on that hand
+#[derive(Clone, Copy, Debug, PartialEq, Event)]
+pub struct XrHapticEvent {
+    pub hand: Hand,
+    /// 0.0 to 1.0
+    pub amplitude: f32,
+    /// in hertz, 0.0 lets the runtime pick the frequency
+    pub frequency: f32,
+    pub duration: Duration,
+}
+
+impl XrHapticEvent {
+    pub fn new(hand: Hand, amplitude: f32, duration: Duration) -> Self {
+        Self {
+            hand,
+            amplitude,
+            frequency: 0.0,
+            duration,
+        }
+    }
+
+    pub fn stop(hand: Hand) -> Self {
+        Self::new(hand, 0.0, Duration::ZERO)
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn is_stop(&self) -> bool {
+        self.amplitude <= 0.0 || self.duration.is_zero()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HapticStep {
+    Pulse {
+        amplitude: f32,
+        frequency: f32,
+        duration: Duration,
+    },
+    /// linearly changes the amplitude over the duration
+    Ramp {
+        from: f32,
+        to: f32,
+        frequency: f32,
+        duration: Duration,
+    },
+    Pause(Duration),
+}
+
+impl HapticStep {
+    pub fn duration(&self) -> Duration {
+        match self {
+            HapticStep::Pulse { duration, .. } => *duration,
+            HapticStep::Ramp { duration, .. } => *duration,
+            HapticStep::Pause(duration) => *duration,
+        }
+    }
+}
+
+/// a sequence of pulses, ramps and pauses played one after another
+///
+/// ```ignore
+/// let grab = HapticPattern::new()
+///     .pulse(0.8, Duration::from_millis(30))
+///     .pause(Duration::from_millis(40))
+///     .ramp(0.8, 0.0, Duration::from_millis(120));
+/// haptics.send(XrHapticPatternEvent::new(Hand::Right, grab));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HapticPattern {
+    pub steps: Vec<HapticStep>,
+}
+
+impl HapticPattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pulse(self, amplitude: f32, duration: Duration) -> Self {
+        self.step(HapticStep::Pulse {
+            amplitude,
+            frequency: 0.0,
+            duration,
+        })
+    }
+
+    pub fn ramp(self, from: f32, to: f32, duration: Duration) -> Self {
+        self.step(HapticStep::Ramp {
+            from,
+            to,
+            frequency: 0.0,
+            duration,
+        })
+    }
+
+    pub fn pause(self, duration: Duration) -> Self {
+        self.step(HapticStep::Pause(duration))
+    }
+
+    pub fn step(mut self, step: HapticStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// repeats the current steps `times` times in total
+    pub fn repeat(mut self, times: usize) -> Self {
+        let steps = self.steps.clone();
+        for _ in 1..times {
+            self.steps.extend_from_slice(&steps);
+        }
+        self
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|step| step.duration()).sum()
+    }
+}
+
+/// plays a pattern on a hand, replacing the pattern that was playing on it
+#[derive(Clone, Debug, PartialEq, Event)]
+pub struct XrHapticPatternEvent {
+    pub hand: Hand,
+    pub pattern: HapticPattern,
+}
+
+impl XrHapticPatternEvent {
+    pub fn new(hand: Hand, pattern: HapticPattern) -> Self {
+        Self { hand, pattern }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ActiveHapticPattern {
+    pub hand: Hand,
+    pub pattern: HapticPattern,
+    pub step: usize,
+    /// time spent in the current step
+    pub elapsed: Duration,
+    started: bool,
+}
+
+impl ActiveHapticPattern {
+    pub fn new(hand: Hand, pattern: HapticPattern) -> Self {
+        Self {
+            hand,
+            pattern,
+            step: 0,
+            elapsed: Duration::ZERO,
+            started: false,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.step >= self.pattern.steps.len()
+    }
+
+    /// moves the pattern `delta` forward, the first call starts the first step, returns the
+    /// vibration to send when the step changed or ramps
+    pub fn advance(&mut self, delta: Duration) -> Option<XrHapticEvent> {
+        //skip over every step that finished during the last frame
+        if self.started {
+            self.elapsed += delta;
+        }
+        while let Some(step) = self.pattern.steps.get(self.step) {
+            if !self.started || self.elapsed < step.duration() {
+                break;
+            }
+            self.elapsed -= step.duration();
+            self.step += 1;
+            self.started = false;
+        }
+        let step = *self.pattern.steps.get(self.step)?;
+        let remaining = step.duration().saturating_sub(self.elapsed);
+        let event = match step {
+            HapticStep::Pulse {
+                amplitude,
+                frequency,
+                ..
+            } if !self.started => {
+                Some(XrHapticEvent::new(self.hand, amplitude, remaining).with_frequency(frequency))
+            }
+            //ramps are resent every frame, every vibration replaces the previous one
+            HapticStep::Ramp {
+                from,
+                to,
+                frequency,
+                duration,
+            } => {
+                let t = if duration.is_zero() {
+                    1.0
+                } else {
+                    self.elapsed.as_secs_f32() / duration.as_secs_f32()
+                };
+                let amplitude = from + (to - from) * t.clamp(0.0, 1.0);
+                Some(XrHapticEvent::new(self.hand, amplitude, remaining).with_frequency(frequency))
+            }
+            HapticStep::Pause(_) if !self.started => Some(XrHapticEvent::stop(self.hand)),
+            //a started pulse or pause keeps going
+            _ => None,
+        };
+        self.started = true;
+        event
+    }
+}
+
+/// the patterns that are currently playing
+#[derive(Resource, Default)]
+pub struct XrHapticPatterns {
+    pub active: Vec<ActiveHapticPattern>,
+}
+
+impl XrHapticPatterns {
+    pub fn is_playing(&self, hand: Hand) -> bool {
+        self.active.iter().any(|pattern| pattern.hand == hand)
+    }
+}
+
+pub fn play_haptic_patterns(
+    time: Res<Time>,
+    mut patterns: ResMut<XrHapticPatterns>,
+    mut pattern_events: EventReader<XrHapticPatternEvent>,
+    mut haptic_events: EventWriter<XrHapticEvent>,
+) {
+    for event in pattern_events.read() {
+        patterns.active.retain(|pattern| pattern.hand != event.hand);
+        patterns
+            .active
+            .push(ActiveHapticPattern::new(event.hand, event.pattern.clone()));
+    }
+    let delta = time.delta();
+    patterns.active.retain_mut(|active| {
+        if let Some(event) = active.advance(delta) {
+            haptic_events.send(event);
+        }
+        !active.finished()
+    });
+}
+
+pub fn apply_haptic_events(
+    mut events: EventReader<XrHapticEvent>,
+    oculus_controller: Res<OculusController>,
+    session: Res<XrSession>,
+) {
+    for event in events.read() {
+        let result = if event.is_stop() {
+            oculus_controller
+                .haptic_feedback
+                .stop_feedback(&session, subaction_path(event.hand))
+        } else {
+            oculus_controller.haptic_feedback.apply_feedback(
+                &session,
+                subaction_path(event.hand),
+                &HapticVibration::new()
+                    .amplitude(event.amplitude.clamp(0.0, 1.0))
+                    .frequency(event.frequency)
+                    .duration(openxr::Duration::from_nanos(
+                        event.duration.as_nanos() as i64
+                    )),
+            )
+        };
+        if let Err(err) = result {
+            warn!("failed to apply haptic feedback: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn pattern_steps_play_in_sequence() {
+        let pattern = HapticPattern::new()
+            .pulse(0.8, ms(30))
+            .pause(ms(40))
+            .ramp(0.8, 0.0, ms(100));
+        let mut active = ActiveHapticPattern::new(Hand::Right, pattern);
+        //the pulse starts right away, whatever the frame time was
+        assert_eq!(
+            active.advance(ms(16)),
+            Some(XrHapticEvent::new(Hand::Right, 0.8, ms(30)))
+        );
+        //a running pulse isn't resent
+        assert_eq!(active.advance(ms(20)), None);
+        //the pause stops the vibration, 10ms into it
+        assert_eq!(
+            active.advance(ms(20)),
+            Some(XrHapticEvent::stop(Hand::Right))
+        );
+        assert_eq!(active.step, 1);
+        assert_eq!(active.elapsed, ms(10));
+        assert_eq!(active.advance(ms(20)), None);
+        //10ms into the ramp
+        let event = active.advance(ms(20)).unwrap();
+        assert_eq!(active.step, 2);
+        assert!((event.amplitude - 0.72).abs() < 1e-6);
+        assert_eq!(event.duration, ms(90));
+        //ramps are resent every frame
+        let event = active.advance(ms(50)).unwrap();
+        assert!((event.amplitude - 0.32).abs() < 1e-6);
+        assert_eq!(event.duration, ms(40));
+        assert!(!active.finished());
+        assert_eq!(active.advance(ms(50)), None);
+        assert!(active.finished());
+    }
+
+    #[test]
+    fn short_steps_play_for_a_frame_and_keep_the_timing() {
+        let pattern = HapticPattern::new()
+            .pulse(0.5, ms(10))
+            .pause(ms(10))
+            .pulse(1.0, ms(30));
+        let mut active = ActiveHapticPattern::new(Hand::Left, pattern);
+        active.advance(ms(16));
+        //the pause already ended during this frame, it still stops the first pulse
+        assert_eq!(
+            active.advance(ms(25)),
+            Some(XrHapticEvent::stop(Hand::Left))
+        );
+        assert_eq!(active.elapsed, ms(15));
+        //the time the pause overran is taken from the next pulse
+        assert_eq!(
+            active.advance(ms(16)),
+            Some(XrHapticEvent::new(Hand::Left, 1.0, ms(9)))
+        );
+        assert_eq!(active.step, 2);
+    }
+
+    #[test]
+    fn ramp_interpolates_the_amplitude() {
+        let mut active =
+            ActiveHapticPattern::new(Hand::Left, HapticPattern::new().ramp(0.2, 1.0, ms(100)));
+        let mut amplitudes = vec![];
+        for _ in 0..4 {
+            amplitudes.push(active.advance(ms(25)).unwrap().amplitude);
+        }
+        for (amplitude, expected) in amplitudes.into_iter().zip([0.2, 0.4, 0.6, 0.8]) {
+            assert!((amplitude - expected).abs() < 1e-6);
+        }
+        assert!(active.advance(ms(25)).is_none());
+    }
+
+    #[test]
+    fn zero_duration_pulse_stops_the_vibration() {
+        let pattern = HapticPattern::new()
+            .pulse(1.0, Duration::ZERO)
+            .pulse(0.5, ms(20));
+        let mut active = ActiveHapticPattern::new(Hand::Left, pattern);
+        let event = active.advance(ms(16)).unwrap();
+        assert!(event.is_stop());
+        assert_eq!(
+            active.advance(ms(16)),
+            Some(XrHapticEvent::new(Hand::Left, 0.5, ms(4)))
+        );
+    }
+}
@@ -1,61 +1,111 @@
+pub mod actions;
+pub mod buttons;
 pub mod controllers;
 pub mod debug_gizmos;
+pub mod gamepad;
+pub mod gestures;
+pub mod haptic_pcm;
+pub mod haptics;
 pub mod interactions;
 pub mod oculus_touch;
 pub mod prototype_locomotion;
+pub mod recording;
+pub mod simulator;
+pub mod spatial_audio;
 pub mod trackers;
 pub mod xr_camera;
 pub mod hand_poses;
 pub mod hand;
+pub mod hand_colliders;
+pub mod hand_mesh;
+pub mod hand_tracking;
 
 use crate::resources::XrSession;
 use crate::xr_begin_frame;
-use crate::xr_input::controllers::XrControllerType;
-use crate::xr_input::oculus_touch::{setup_oculus_controller, ActionSets};
+use crate::xr_input::actions::{XrActionsPlugin, XrControllerActions};
+use crate::xr_input::buttons::XrButtonsPlugin;
+use crate::xr_input::controllers::{XrControllerInputSet, XrControllerType};
+use crate::xr_input::haptics::XrHapticsPlugin;
+use crate::xr_input::oculus_touch::{ActionSets, OculusController};
 use crate::xr_input::xr_camera::{xr_camera_head_sync, Eye, XRProjection, XrCameraBundle};
 use bevy::app::{App, PostUpdate, Startup};
 use bevy::log::warn;
-use bevy::prelude::{BuildChildren, IntoSystemConfigs, Component};
+use bevy::prelude::{resource_exists, BuildChildren, Component, IntoSystemConfigs};
 use bevy::prelude::{Commands, Plugin, PreUpdate, Quat, Res, SpatialBundle, Update, Vec3};
 use bevy::render::camera::CameraProjectionPlugin;
 use bevy::render::view::{update_frusta, VisibilitySystems};
 use bevy::transform::TransformSystem;
+use serde::{Deserialize, Serialize};
 
 use self::trackers::{
-    adopt_open_xr_trackers, update_open_xr_controllers, OpenXRLeftEye, OpenXRRightEye,
-    OpenXRTrackingRoot,
+    adopt_open_xr_trackers, spawn_aim_entities, spawn_controller_trackers, update_aim_entities,
+    update_open_xr_controllers, update_open_xr_hmd, update_tracking_states,
+    update_xr_controller_input, OpenXRHMD, OpenXRLeftEye, OpenXRRightEye, OpenXRTrackingRoot,
+    XrTrackerSettings, XrTrackingLost, XrTrackingRegained, XrTrackingStates, XrVelocity,
 };
 
-#[derive(Copy, Clone)]
-pub struct OpenXrInput {
-    pub controller_type: XrControllerType,
+/// Controller trackers, buttons, haptics and the built in controller actions, every
+/// `XrControllerType` is bound and the runtime picks the profile that matches the hardware.
+#[derive(Copy, Clone, Default)]
+pub struct OpenXrInput;
+
+impl OpenXrInput {
+    #[deprecated(note = "every `XrControllerType` is bound now, use `OpenXrInput` directly")]
+    pub fn new(_controller_type: XrControllerType) -> Self {
+        Self
+    }
 }
-#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq, Component)]
+
+#[derive(
+    Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Serialize, Deserialize,
+)]
 pub enum Hand {
     Left,
     Right,
 }
 
-impl OpenXrInput {
-    pub fn new(controller_type: XrControllerType) -> Self {
-        Self { controller_type }
-    }
-}
-
 impl Plugin for OpenXrInput {
     fn build(&self, app: &mut App) {
         app.add_plugins(CameraProjectionPlugin::<XRProjection>::default());
-        match self.controller_type {
-            XrControllerType::OculusTouch => {
-                app.add_systems(Startup, setup_oculus_controller);
-            }
-        }
-        //adopt any new trackers
+        //the controller actions are created by the actions plugin, together with user actions
+        app.insert_resource(XrControllerActions);
+        app.add_plugins(XrActionsPlugin);
+        app.add_plugins(XrHapticsPlugin);
+        //spawn the controller trackers and adopt any new ones
+        app.init_resource::<XrTrackerSettings>();
         app.add_systems(PreUpdate, adopt_open_xr_trackers);
+        app.add_systems(PreUpdate, spawn_aim_entities);
         app.add_systems(PreUpdate, action_set_system);
-        app.add_systems(PreUpdate, xr_camera_head_sync.after(xr_begin_frame));
+        //buttons and axes as bevy inputs
+        app.add_plugins(XrButtonsPlugin);
+        app.add_systems(
+            PreUpdate,
+            update_xr_controller_input
+                .in_set(XrControllerInputSet)
+                .after(action_set_system)
+                .run_if(resource_exists::<OculusController>()),
+        );
+        app.add_systems(PreUpdate, update_open_xr_hmd.after(xr_begin_frame));
+        app.add_systems(
+            PreUpdate,
+            xr_camera_head_sync
+                .after(xr_begin_frame)
+                .after(update_open_xr_hmd),
+        );
         //update controller trackers
-        app.add_systems(Update, update_open_xr_controllers);
+        //without the controller actions, e.g. when creating them failed, there is nothing to update
+        app.add_systems(
+            Update,
+            update_open_xr_controllers.run_if(resource_exists::<OculusController>()),
+        );
+        app.add_systems(Update, update_aim_entities.after(update_open_xr_controllers));
+        app.init_resource::<XrTrackingStates>();
+        app.add_event::<XrTrackingLost>();
+        app.add_event::<XrTrackingRegained>();
+        app.add_systems(
+            Update,
+            update_tracking_states.after(update_open_xr_controllers),
+        );
         app.add_systems(
             PostUpdate,
             update_frusta::<XRProjection>
@@ -66,19 +116,25 @@ impl Plugin for OpenXrInput {
     }
 }
 
-fn setup_xr_cameras(mut commands: Commands) {
+fn setup_xr_cameras(mut commands: Commands, tracker_settings: Res<XrTrackerSettings>) {
     //this needs to do the whole xr tracking volume not just cameras
     //get the root?
     let tracking_root = commands
         .spawn((SpatialBundle::default(), OpenXRTrackingRoot))
         .id();
+    //the hmd follows the VIEW space, the eyes are offset from it
+    let hmd = commands
+        .spawn((SpatialBundle::default(), OpenXRHMD, XrVelocity::default()))
+        .id();
     let right = commands
         .spawn((XrCameraBundle::new(Eye::Right), OpenXRRightEye))
         .id();
     let left = commands
         .spawn((XrCameraBundle::new(Eye::Left), OpenXRLeftEye))
         .id();
-    commands.entity(tracking_root).push_children(&[right, left]);
+    commands.entity(tracking_root).add_child(hmd);
+    commands.entity(hmd).push_children(&[right, left]);
+    spawn_controller_trackers(&mut commands, tracking_root, &tracker_settings);
 }
 
 fn action_set_system(action_sets: Res<ActionSets>, session: Res<XrSession>) {
@@ -1,34 +1,15 @@
 use crate::input::XrInput;
-use crate::resources::{XrInstance, XrSession};
-use crate::xr_input::controllers::{Handed, Touchable};
+use crate::xr_input::actions::SuggestedBindings;
+use crate::xr_input::controllers::{Handed, Touchable, XrControllerType};
 use crate::xr_input::Hand;
-use bevy::prelude::{Commands, Res, Resource};
+use bevy::prelude::Resource;
 use openxr::{
-    Action, ActionSet, AnyGraphics, Binding, FrameState, Haptic, Instance, Path, Posef, Session,
-    Space, SpaceLocation, SpaceVelocity,
+    Action, ActionSet, AnyGraphics, FrameState, Haptic, Instance, Path, Posef, Session, Space,
+    SpaceLocation, SpaceVelocity,
 };
 
 use std::sync::OnceLock;
 
-pub fn setup_oculus_controller(
-    mut commands: Commands,
-    instance: Res<XrInstance>,
-    session: Res<XrSession>,
-) {
-    let mut action_sets = vec![];
-    let oculus_controller = OculusController::new(
-        Instance::clone(&instance),
-        Session::clone(&session),
-        &mut action_sets,
-    )
-    .unwrap();
-    session
-        .attach_action_sets(&action_sets.iter().map(|a| a).collect::<Vec<_>>())
-        .unwrap();
-    commands.insert_resource(oculus_controller);
-    commands.insert_resource(ActionSets(action_sets));
-}
-
 #[derive(Resource, Clone)]
 pub struct ActionSets(pub Vec<ActionSet>);
 
@@ -82,6 +63,48 @@ impl OculusControllerRef<'_> {
         }
         .unwrap()
     }
+    /// the pinch point of a tracked hand, only bound for `XrControllerType::HandInteraction`
+    pub fn pinch_space(&self, hand: Hand) -> (SpaceLocation, SpaceVelocity) {
+        match hand {
+            Hand::Left => self.oculus_controller.pinch_space.left.relate(
+                &self.xr_input.stage,
+                self.frame_state.predicted_display_time,
+            ),
+            Hand::Right => self.oculus_controller.pinch_space.right.relate(
+                &self.xr_input.stage,
+                self.frame_state.predicted_display_time,
+            ),
+        }
+        .unwrap()
+    }
+    /// the index fingertip of a tracked hand, only bound for `XrControllerType::HandInteraction`
+    pub fn poke_space(&self, hand: Hand) -> (SpaceLocation, SpaceVelocity) {
+        match hand {
+            Hand::Left => self.oculus_controller.poke_space.left.relate(
+                &self.xr_input.stage,
+                self.frame_state.predicted_display_time,
+            ),
+            Hand::Right => self.oculus_controller.poke_space.right.relate(
+                &self.xr_input.stage,
+                self.frame_state.predicted_display_time,
+            ),
+        }
+        .unwrap()
+    }
+    /// the center of the palm, only bound with `XR_EXT_palm_pose`
+    pub fn palm_space(&self, hand: Hand) -> (SpaceLocation, SpaceVelocity) {
+        match hand {
+            Hand::Left => self.oculus_controller.palm_space.left.relate(
+                &self.xr_input.stage,
+                self.frame_state.predicted_display_time,
+            ),
+            Hand::Right => self.oculus_controller.palm_space.right.relate(
+                &self.xr_input.stage,
+                self.frame_state.predicted_display_time,
+            ),
+        }
+        .unwrap()
+    }
     pub fn squeeze(&self, hand: Hand) -> f32 {
         let action = &self.oculus_controller.squeeze;
         action
@@ -212,6 +235,43 @@ impl OculusControllerRef<'_> {
             .unwrap()
             .current_state
     }
+    /// whether a finger is near the trigger, `None` without `XR_FB_touch_controller_proximity`
+    pub fn trigger_proximity(&self, hand: Hand) -> Option<bool> {
+        if touch_controller_proximity_supported(self.instance) {
+            Some(
+                self.oculus_controller
+                    .trigger_proximity
+                    .state(&self.session, subaction_path(hand))
+                    .unwrap()
+                    .current_state,
+            )
+        } else {
+            None
+        }
+    }
+    /// whether the thumb is near the thumbrest, the stick or the buttons, `None` without
+    /// `XR_FB_touch_controller_proximity`
+    pub fn thumb_proximity(&self, hand: Hand) -> Option<bool> {
+        if touch_controller_proximity_supported(self.instance) {
+            Some(
+                self.oculus_controller
+                    .thumb_proximity
+                    .state(&self.session, subaction_path(hand))
+                    .unwrap()
+                    .current_state,
+            )
+        } else {
+            None
+        }
+    }
+}
+
+pub fn touch_controller_proximity_supported(instance: &Instance) -> bool {
+    instance.exts().fb_touch_controller_proximity.is_some()
+}
+
+pub fn palm_pose_supported(instance: &Instance) -> bool {
+    instance.exts().ext_palm_pose.is_some()
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -243,8 +303,14 @@ impl OculusController {
 pub struct OculusController {
     pub grip_space: Handed<Space>,
     pub aim_space: Handed<Space>,
+    pub pinch_space: Handed<Space>,
+    pub poke_space: Handed<Space>,
+    pub palm_space: Handed<Space>,
     pub grip_pose: Action<Posef>,
     pub aim_pose: Action<Posef>,
+    pub pinch_pose: Action<Posef>,
+    pub poke_pose: Action<Posef>,
+    pub palm_pose: Action<Posef>,
     pub squeeze: Action<f32>,
     pub trigger: Touchable<f32>,
     pub haptic_feedback: Action<Haptic>,
@@ -258,6 +324,8 @@ pub struct OculusController {
     pub thumbstick_touch: Action<bool>,
     pub thumbstick_click: Action<bool>,
     pub thumbrest_touch: Action<bool>,
+    pub trigger_proximity: Action<bool>,
+    pub thumb_proximity: Action<bool>,
 }
 impl OculusController {
     pub fn new(
@@ -273,6 +341,9 @@ impl OculusController {
         let hands = [left_path, right_path];
         let grip_pose = action_set.create_action::<Posef>("hand_pose", "Hand Pose", &hands)?;
         let aim_pose = action_set.create_action::<Posef>("pointer_pose", "Pointer Pose", &hands)?;
+        let pinch_pose = action_set.create_action::<Posef>("pinch_pose", "Pinch Pose", &hands)?;
+        let poke_pose = action_set.create_action::<Posef>("poke_pose", "Poke Pose", &hands)?;
+        let palm_pose = action_set.create_action::<Posef>("palm_pose", "Palm Pose", &hands)?;
 
         let this = OculusController {
             grip_space: Handed {
@@ -283,8 +354,23 @@ impl OculusController {
                 left: aim_pose.create_space(session.clone(), left_path, Posef::IDENTITY)?,
                 right: aim_pose.create_space(session.clone(), right_path, Posef::IDENTITY)?,
             },
+            pinch_space: Handed {
+                left: pinch_pose.create_space(session.clone(), left_path, Posef::IDENTITY)?,
+                right: pinch_pose.create_space(session.clone(), right_path, Posef::IDENTITY)?,
+            },
+            poke_space: Handed {
+                left: poke_pose.create_space(session.clone(), left_path, Posef::IDENTITY)?,
+                right: poke_pose.create_space(session.clone(), right_path, Posef::IDENTITY)?,
+            },
+            palm_space: Handed {
+                left: palm_pose.create_space(session.clone(), left_path, Posef::IDENTITY)?,
+                right: palm_pose.create_space(session.clone(), right_path, Posef::IDENTITY)?,
+            },
             grip_pose,
             aim_pose,
+            pinch_pose,
+            poke_pose,
+            palm_pose,
             squeeze: action_set.create_action("squeeze", "Grip Pull", &hands)?,
             trigger: Touchable {
                 inner: action_set.create_action("trigger", "Trigger Pull", &hands)?,
@@ -329,139 +415,153 @@ impl OculusController {
                 "Thumbrest Touch",
                 &hands,
             )?,
+            trigger_proximity: action_set.create_action(
+                "trigger_proximity",
+                "Trigger Proximity",
+                &hands,
+            )?,
+            thumb_proximity: action_set.create_action(
+                "thumb_proximity",
+                "Thumb Proximity",
+                &hands,
+            )?,
         };
-        let i = instance;
-        i.suggest_interaction_profile_bindings(
-            i.string_to_path("/interaction_profiles/oculus/touch_controller")?,
-            &[
-                Binding::new(
-                    &this.grip_pose,
-                    i.string_to_path("/user/hand/left/input/grip/pose")?,
-                ),
-                Binding::new(
-                    &this.grip_pose,
-                    i.string_to_path("/user/hand/right/input/grip/pose")?,
-                ),
-                Binding::new(
-                    &this.aim_pose,
-                    i.string_to_path("/user/hand/left/input/aim/pose")?,
-                ),
-                Binding::new(
-                    &this.aim_pose,
-                    i.string_to_path("/user/hand/left/input/aim/pose")?,
-                ),
-                Binding::new(
-                    &this.squeeze,
-                    i.string_to_path("/user/hand/left/input/squeeze/value")?,
-                ),
-                Binding::new(
-                    &this.squeeze,
-                    i.string_to_path("/user/hand/right/input/squeeze/value")?,
-                ),
-                Binding::new(
-                    &this.trigger.inner,
-                    i.string_to_path("/user/hand/right/input/trigger/value")?,
-                ),
-                Binding::new(
-                    &this.trigger.inner,
-                    i.string_to_path("/user/hand/left/input/trigger/value")?,
-                ),
-                Binding::new(
-                    &this.trigger.touch,
-                    i.string_to_path("/user/hand/right/input/trigger/touch")?,
-                ),
-                Binding::new(
-                    &this.trigger.touch,
-                    i.string_to_path("/user/hand/left/input/trigger/touch")?,
-                ),
-                Binding::new(
-                    &this.haptic_feedback,
-                    i.string_to_path("/user/hand/right/output/haptic")?,
-                ),
-                Binding::new(
-                    &this.haptic_feedback,
-                    i.string_to_path("/user/hand/left/output/haptic")?,
-                ),
-                Binding::new(
-                    &this.x_button.inner,
-                    i.string_to_path("/user/hand/left/input/x/click")?,
-                ),
-                Binding::new(
-                    &this.x_button.touch,
-                    i.string_to_path("/user/hand/left/input/x/touch")?,
-                ),
-                Binding::new(
-                    &this.y_button.inner,
-                    i.string_to_path("/user/hand/left/input/y/click")?,
-                ),
-                Binding::new(
-                    &this.y_button.touch,
-                    i.string_to_path("/user/hand/left/input/y/touch")?,
-                ),
-                Binding::new(
-                    &this.menu_button,
-                    i.string_to_path("/user/hand/left/input/menu/click")?,
-                ),
-                Binding::new(
-                    &this.a_button.inner,
-                    i.string_to_path("/user/hand/right/input/a/click")?,
-                ),
-                Binding::new(
-                    &this.a_button.touch,
-                    i.string_to_path("/user/hand/right/input/a/touch")?,
-                ),
-                Binding::new(
-                    &this.b_button.inner,
-                    i.string_to_path("/user/hand/right/input/b/click")?,
-                ),
-                Binding::new(
-                    &this.b_button.touch,
-                    i.string_to_path("/user/hand/right/input/b/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_x,
-                    i.string_to_path("/user/hand/left/input/thumbstick/x")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_x,
-                    i.string_to_path("/user/hand/right/input/thumbstick/x")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_y,
-                    i.string_to_path("/user/hand/left/input/thumbstick/y")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_y,
-                    i.string_to_path("/user/hand/right/input/thumbstick/y")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_click,
-                    i.string_to_path("/user/hand/left/input/thumbstick/click")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_click,
-                    i.string_to_path("/user/hand/right/input/thumbstick/click")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_touch,
-                    i.string_to_path("/user/hand/left/input/thumbstick/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_touch,
-                    i.string_to_path("/user/hand/right/input/thumbstick/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbrest_touch,
-                    i.string_to_path("/user/hand/left/input/thumbrest/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbrest_touch,
-                    i.string_to_path("/user/hand/right/input/thumbrest/touch")?,
-                ),
-            ],
-        )?;
 
         action_sets.push(action_set);
         Ok(this)
     }
+
+    /// adds the bindings of every `XrControllerType` to the other actions' bindings, profiles the
+    /// runtime doesn't support are skipped with a warning
+    pub fn suggest_bindings<'a>(
+        &'a self,
+        instance: &Instance,
+        bindings: &mut SuggestedBindings<'a>,
+    ) -> anyhow::Result<()> {
+        for controller in XrControllerType::ALL {
+            let mut b = bindings.profile(instance, controller.interaction_profile())?;
+            //every profile has poses, only the controllers have haptics
+            b.hands(&self.grip_pose, "input/grip/pose")?;
+            b.hands(&self.aim_pose, "input/aim/pose")?;
+            if controller != XrControllerType::HandInteraction {
+                b.hands(&self.haptic_feedback, "output/haptic")?;
+            }
+            //the palm pose is only added to the core profiles and the hand interaction one
+            let palm_profile = !matches!(
+                controller,
+                XrControllerType::Pico4 | XrControllerType::HpReverbG2
+            );
+            if palm_profile && palm_pose_supported(instance) {
+                b.hands(&self.palm_pose, "input/palm_ext/pose")?;
+            }
+            match controller {
+                XrControllerType::OculusTouch => {
+                    b.hands(&self.squeeze, "input/squeeze/value")?;
+                    b.hands(&self.trigger.inner, "input/trigger/value")?;
+                    b.hands(&self.trigger.touch, "input/trigger/touch")?;
+                    b.left(&self.x_button.inner, "input/x/click")?;
+                    b.left(&self.x_button.touch, "input/x/touch")?;
+                    b.left(&self.y_button.inner, "input/y/click")?;
+                    b.left(&self.y_button.touch, "input/y/touch")?;
+                    b.left(&self.menu_button, "input/menu/click")?;
+                    b.right(&self.a_button.inner, "input/a/click")?;
+                    b.right(&self.a_button.touch, "input/a/touch")?;
+                    b.right(&self.b_button.inner, "input/b/click")?;
+                    b.right(&self.b_button.touch, "input/b/touch")?;
+                    b.hands(&self.thumbstick_x, "input/thumbstick/x")?;
+                    b.hands(&self.thumbstick_y, "input/thumbstick/y")?;
+                    b.hands(&self.thumbstick_click, "input/thumbstick/click")?;
+                    b.hands(&self.thumbstick_touch, "input/thumbstick/touch")?;
+                    b.hands(&self.thumbrest_touch, "input/thumbrest/touch")?;
+                    if touch_controller_proximity_supported(instance) {
+                        b.hands(&self.trigger_proximity, "input/trigger/proximity_fb")?;
+                        b.hands(&self.thumb_proximity, "input/thumb_fb/proximity_fb")?;
+                    }
+                }
+                XrControllerType::SimpleController => {
+                    b.hands(&self.trigger.inner, "input/select/click")?;
+                    b.hands(&self.menu_button, "input/menu/click")?;
+                }
+                XrControllerType::ValveIndex => {
+                    //the menu button is the reserved system button here
+                    b.hands(&self.squeeze, "input/squeeze/value")?;
+                    b.hands(&self.trigger.inner, "input/trigger/value")?;
+                    b.hands(&self.trigger.touch, "input/trigger/touch")?;
+                    b.left(&self.x_button.inner, "input/a/click")?;
+                    b.left(&self.x_button.touch, "input/a/touch")?;
+                    b.left(&self.y_button.inner, "input/b/click")?;
+                    b.left(&self.y_button.touch, "input/b/touch")?;
+                    b.right(&self.a_button.inner, "input/a/click")?;
+                    b.right(&self.a_button.touch, "input/a/touch")?;
+                    b.right(&self.b_button.inner, "input/b/click")?;
+                    b.right(&self.b_button.touch, "input/b/touch")?;
+                    b.hands(&self.thumbstick_x, "input/thumbstick/x")?;
+                    b.hands(&self.thumbstick_y, "input/thumbstick/y")?;
+                    b.hands(&self.thumbstick_click, "input/thumbstick/click")?;
+                    b.hands(&self.thumbstick_touch, "input/thumbstick/touch")?;
+                    b.hands(&self.thumbrest_touch, "input/trackpad/touch")?;
+                }
+                XrControllerType::HtcVive => {
+                    //the trackpad stands in for the thumbstick
+                    b.hands(&self.squeeze, "input/squeeze/click")?;
+                    b.hands(&self.trigger.inner, "input/trigger/value")?;
+                    b.left(&self.menu_button, "input/menu/click")?;
+                    b.hands(&self.thumbstick_x, "input/trackpad/x")?;
+                    b.hands(&self.thumbstick_y, "input/trackpad/y")?;
+                    b.hands(&self.thumbstick_click, "input/trackpad/click")?;
+                    b.hands(&self.thumbstick_touch, "input/trackpad/touch")?;
+                }
+                XrControllerType::WindowsMixedReality => {
+                    b.hands(&self.squeeze, "input/squeeze/click")?;
+                    b.hands(&self.trigger.inner, "input/trigger/value")?;
+                    b.left(&self.menu_button, "input/menu/click")?;
+                    b.left(&self.x_button.inner, "input/trackpad/click")?;
+                    b.right(&self.a_button.inner, "input/trackpad/click")?;
+                    b.hands(&self.thumbstick_x, "input/thumbstick/x")?;
+                    b.hands(&self.thumbstick_y, "input/thumbstick/y")?;
+                    b.hands(&self.thumbstick_click, "input/thumbstick/click")?;
+                    b.hands(&self.thumbrest_touch, "input/trackpad/touch")?;
+                }
+                XrControllerType::Pico4 => {
+                    b.hands(&self.squeeze, "input/squeeze/value")?;
+                    b.hands(&self.trigger.inner, "input/trigger/value")?;
+                    b.hands(&self.trigger.touch, "input/trigger/touch")?;
+                    b.left(&self.x_button.inner, "input/x/click")?;
+                    b.left(&self.x_button.touch, "input/x/touch")?;
+                    b.left(&self.y_button.inner, "input/y/click")?;
+                    b.left(&self.y_button.touch, "input/y/touch")?;
+                    b.left(&self.menu_button, "input/menu/click")?;
+                    b.right(&self.a_button.inner, "input/a/click")?;
+                    b.right(&self.a_button.touch, "input/a/touch")?;
+                    b.right(&self.b_button.inner, "input/b/click")?;
+                    b.right(&self.b_button.touch, "input/b/touch")?;
+                    b.hands(&self.thumbstick_x, "input/thumbstick/x")?;
+                    b.hands(&self.thumbstick_y, "input/thumbstick/y")?;
+                    b.hands(&self.thumbstick_click, "input/thumbstick/click")?;
+                    b.hands(&self.thumbstick_touch, "input/thumbstick/touch")?;
+                }
+                XrControllerType::HpReverbG2 => {
+                    b.hands(&self.squeeze, "input/squeeze/value")?;
+                    b.hands(&self.trigger.inner, "input/trigger/value")?;
+                    b.left(&self.x_button.inner, "input/x/click")?;
+                    b.left(&self.y_button.inner, "input/y/click")?;
+                    b.left(&self.menu_button, "input/menu/click")?;
+                    b.right(&self.a_button.inner, "input/a/click")?;
+                    b.right(&self.b_button.inner, "input/b/click")?;
+                    b.hands(&self.thumbstick_x, "input/thumbstick/x")?;
+                    b.hands(&self.thumbstick_y, "input/thumbstick/y")?;
+                    b.hands(&self.thumbstick_click, "input/thumbstick/click")?;
+                }
+                XrControllerType::HandInteraction => {
+                    //pinching is the select of hand tracking, ready is set before it closes
+                    b.hands(&self.trigger.inner, "input/pinch_ext/value")?;
+                    b.hands(&self.trigger.touch, "input/pinch_ext/ready_ext")?;
+                    b.hands(&self.squeeze, "input/grasp_ext/value")?;
+                    b.hands(&self.pinch_pose, "input/pinch_ext/pose")?;
+                    b.hands(&self.poke_pose, "input/poke_ext/pose")?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
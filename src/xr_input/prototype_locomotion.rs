@@ -5,13 +5,12 @@ use bevy::{
     time::{Time, Timer, TimerMode},
 };
 
-use crate::{
-    input::XrInput,
-    resources::{XrFrameState, XrInstance, XrSession, XrViews},
-};
+use crate::resources::XrViews;
 
 use super::{
-    oculus_touch::OculusController, trackers::OpenXRTrackingRoot, Hand, QuatConv, Vec3Conv,
+    controllers::XrControllerInput,
+    trackers::{OpenXRLeftController, OpenXRRightController, OpenXRTrackingRoot},
+    QuatConv, Vec3Conv,
 };
 
 pub enum LocomotionType {
@@ -59,11 +58,14 @@ impl Default for PrototypeLocomotionConfig {
 pub fn proto_locomotion(
     time: Res<Time>,
     mut tracking_root_query: Query<(&mut Transform, With<OpenXRTrackingRoot>)>,
-    oculus_controller: Res<OculusController>,
-    frame_state: Res<XrFrameState>,
-    xr_input: Res<XrInput>,
-    instance: Res<XrInstance>,
-    session: Res<XrSession>,
+    left_controller_query: Query<
+        (&Transform, &XrControllerInput),
+        (With<OpenXRLeftController>, Without<OpenXRTrackingRoot>),
+    >,
+    right_controller_query: Query<
+        &XrControllerInput,
+        (With<OpenXRRightController>, Without<OpenXRTrackingRoot>),
+    >,
     views: ResMut<XrViews>,
     mut gizmos: Gizmos,
     config_option: Option<ResMut<PrototypeLocomotionConfig>>,
@@ -77,15 +79,20 @@ pub fn proto_locomotion(
     }
     //i hate this but im too tired to think
     let mut config = config_option.unwrap();
-    //lock frame
-    let frame_state = *frame_state.lock().unwrap();
-    //get controller
-    let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
+    //get controllers
+    let (left_grip, left_input) = match left_controller_query.get_single() {
+        Ok(left) => left,
+        Err(_) => return,
+    };
+    let right_input = match right_controller_query.get_single() {
+        Ok(right) => right,
+        Err(_) => return,
+    };
     let root = tracking_root_query.get_single_mut();
     match root {
         Ok(mut position) => {
             //get the stick input and do some maths
-            let stick = controller.thumbstick(Hand::Left);
+            let stick = left_input.thumbstick;
             let input = Vec3::new(stick.x, 0.0, -stick.y);
 
             let mut reference_quat = Quat::IDENTITY;
@@ -105,13 +112,8 @@ pub fn proto_locomotion(
                     }
                 }
                 LocomotionType::Hand => {
-                    let grip = controller.grip_space(Hand::Left);
-                    reference_quat = grip
-                        .0
-                        .pose
-                        .orientation
-                        .to_quat()
-                        .mul_quat(position.0.rotation);
+                    //the grip transform is relative to the tracking root
+                    reference_quat = position.0.rotation.mul_quat(left_grip.rotation);
                 }
             }
             //TODO: do this correctly as just removing the y from the resultant vec3 isnt correct, but works well enough for now
@@ -125,7 +127,7 @@ pub fn proto_locomotion(
             match config.rotation_type {
                 RotationType::Smooth => {
                     //once again with the math
-                    let control_stick = controller.thumbstick(Hand::Right);
+                    let control_stick = right_input.thumbstick;
                     let rot_input = -control_stick.x; //why is this negative i dont know
                     if rot_input.abs() <= config.rotation_stick_deadzone {
                         return;
@@ -154,7 +156,7 @@ pub fn proto_locomotion(
                     if config.rotation_timer.timer.finished() {
                         //now we can snap turn?
                         //once again with the math
-                        let control_stick = controller.thumbstick(Hand::Right);
+                        let control_stick = right_input.thumbstick;
                         let rot_input = -control_stick.x;
                         if rot_input.abs() <= config.rotation_stick_deadzone {
                             return;
@@ -0,0 +1,718 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use openxr::Posef;
+use serde::{Deserialize, Serialize};
+
+use crate::resources::{XrFrameState, XrViews};
+
+use super::{
+    controllers::{XrControllerInput, XrControllerInputSet},
+    hand::HandState,
+    simulator::{update_simulated_controller_input, update_simulated_trackers, XrSimulatedHead},
+    trackers::{
+        update_aim_entities, update_open_xr_controllers, AimPose, OpenXRController,
+        OpenXRLeftController, OpenXRRightController,
+    },
+    Hand, QuatConv, Vec3Conv,
+};
+
+/// records the headset views, controller poses and controller input of every frame,
+/// the frames are streamed to `path` and flushed every `flush_interval`, so a crash only loses
+/// the last interval
+pub struct XrInputRecorderPlugin {
+    pub path: PathBuf,
+    /// start recording when the app starts, otherwise call [`XrInputRecorder::start`]
+    pub autostart: bool,
+    pub flush_interval: Duration,
+}
+
+impl XrInputRecorderPlugin {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            autostart: true,
+            flush_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Plugin for XrInputRecorderPlugin {
+    fn build(&self, app: &mut App) {
+        let mut recorder = XrInputRecorder::new(self.path.clone());
+        recorder.flush_interval = self.flush_interval;
+        //the file is only replaced once the app runs, not when the plugin is added
+        if self.autostart {
+            app.add_systems(Startup, start_recording);
+        }
+        app.insert_resource(recorder)
+            .add_systems(
+                Update,
+                record_xr_input
+                    .after(update_open_xr_controllers)
+                    .run_if(resource_exists::<XrFrameState>()),
+            )
+            .add_systems(Last, stop_recording_on_exit);
+    }
+}
+
+/// replays a recording made with [`XrInputRecorderPlugin`] at the speed it was recorded, every
+/// app update replays the last frame whose display time has passed, add it next to the
+/// `XrSimulatorPlugin` to play a recording back without a headset
+pub struct XrInputReplayPlugin {
+    pub path: PathBuf,
+    pub looping: bool,
+}
+
+impl XrInputReplayPlugin {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            looping: false,
+        }
+    }
+}
+
+impl Plugin for XrInputReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let recording = match XrInputRecording::load(&self.path) {
+            Ok(recording) => recording,
+            Err(err) => {
+                error!("failed to load xr input recording {:?}: {}", self.path, err);
+                XrInputRecording::default()
+            }
+        };
+        //input is replayed with the other controller input, poses after the simulator moved the
+        //trackers
+        app.insert_resource(XrInputReplay::new(recording, self.looping))
+            .add_systems(
+                PreUpdate,
+                replay_xr_input
+                    .in_set(XrControllerInputSet)
+                    .after(update_simulated_controller_input),
+            )
+            .add_systems(
+                Update,
+                replay_xr_poses
+                    .after(update_simulated_trackers)
+                    .before(update_aim_entities),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPose {
+    pub position: [f32; 3],
+    pub orientation: [f32; 4],
+}
+
+impl RecordedPose {
+    pub fn to_transform(&self) -> Transform {
+        Transform {
+            translation: Vec3::from_array(self.position),
+            rotation: Quat::from_array(self.orientation),
+            ..default()
+        }
+    }
+}
+
+impl From<Transform> for RecordedPose {
+    fn from(transform: Transform) -> Self {
+        Self {
+            position: transform.translation.to_array(),
+            orientation: transform.rotation.to_array(),
+        }
+    }
+}
+
+impl From<Posef> for RecordedPose {
+    fn from(pose: Posef) -> Self {
+        Self {
+            position: pose.position.to_vec3().to_array(),
+            orientation: pose.orientation.to_quat().to_array(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedView {
+    pub pose: RecordedPose,
+    /// left, right, up and down angles in radians
+    pub fov: [f32; 4],
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedControllerState {
+    pub grip: RecordedPose,
+    pub aim: RecordedPose,
+    pub squeeze: f32,
+    pub trigger: f32,
+    pub trigger_touched: bool,
+    /// the A button on the right controller and the X button on the left one
+    pub primary_button: bool,
+    pub primary_touched: bool,
+    /// the B button on the right controller and the Y button on the left one
+    pub secondary_button: bool,
+    pub secondary_touched: bool,
+    pub thumbstick_x: f32,
+    pub thumbstick_y: f32,
+    pub thumbstick_click: bool,
+    pub thumbstick_touch: bool,
+    pub thumbrest_touch: bool,
+    #[serde(default)]
+    pub trigger_proximity: Option<bool>,
+    #[serde(default)]
+    pub thumb_proximity: Option<bool>,
+}
+
+impl RecordedControllerState {
+    pub fn from_input(grip: Transform, aim: Transform, input: &XrControllerInput) -> Self {
+        Self {
+            grip: grip.into(),
+            aim: aim.into(),
+            squeeze: input.squeeze,
+            trigger: input.trigger,
+            trigger_touched: input.trigger_touched,
+            primary_button: input.primary_button,
+            primary_touched: input.primary_touched,
+            secondary_button: input.secondary_button,
+            secondary_touched: input.secondary_touched,
+            thumbstick_x: input.thumbstick.x,
+            thumbstick_y: input.thumbstick.y,
+            thumbstick_click: input.thumbstick_click,
+            thumbstick_touch: input.thumbstick_touched,
+            thumbrest_touch: input.thumbrest_touched,
+            trigger_proximity: input.trigger_proximity,
+            thumb_proximity: input.thumb_proximity,
+        }
+    }
+
+    /// the menu button is stored on the frame, pass it in for the left controller
+    pub fn to_input(&self, menu_button: bool) -> XrControllerInput {
+        XrControllerInput {
+            trigger: self.trigger,
+            trigger_touched: self.trigger_touched,
+            squeeze: self.squeeze,
+            primary_button: self.primary_button,
+            primary_touched: self.primary_touched,
+            secondary_button: self.secondary_button,
+            secondary_touched: self.secondary_touched,
+            menu_button,
+            thumbstick: Vec2::new(self.thumbstick_x, self.thumbstick_y),
+            thumbstick_click: self.thumbstick_click,
+            thumbstick_touched: self.thumbstick_touch,
+            thumbrest_touched: self.thumbrest_touch,
+            trigger_proximity: self.trigger_proximity,
+            thumb_proximity: self.thumb_proximity,
+        }
+    }
+
+    pub fn hand_state(&self) -> HandState {
+        self.to_input(false).hand_state()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// predicted display time in nanoseconds
+    pub predicted_display_time: i64,
+    pub views: Vec<RecordedView>,
+    pub left: RecordedControllerState,
+    pub right: RecordedControllerState,
+    pub menu_button: bool,
+}
+
+impl RecordedFrame {
+    /// the head pose, halfway between the eyes
+    pub fn head_transform(&self) -> Option<Transform> {
+        let first = self.views.first()?.pose.to_transform();
+        let last = self.views.last()?.pose.to_transform();
+        Some(Transform {
+            translation: first.translation.lerp(last.translation, 0.5),
+            rotation: first.rotation.slerp(last.rotation, 0.5),
+            ..default()
+        })
+    }
+
+    pub fn controller(&self, hand: Hand) -> &RecordedControllerState {
+        match hand {
+            Hand::Left => &self.left,
+            Hand::Right => &self.right,
+        }
+    }
+
+    pub fn controller_input(&self, hand: Hand) -> XrControllerInput {
+        match hand {
+            Hand::Left => self.left.to_input(self.menu_button),
+            Hand::Right => self.right.to_input(false),
+        }
+    }
+}
+
+/// a recording is stored as one RON frame per line, so frames can be appended while recording
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct XrInputRecording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl XrInputRecording {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// a cut off last line, left by a crash while recording, is skipped
+    pub fn read(reader: impl BufRead) -> anyhow::Result<Self> {
+        let lines = reader
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>();
+        let mut frames = Vec::with_capacity(lines.len());
+        for (index, line) in lines.iter().enumerate() {
+            match ron::de::from_str(line) {
+                Ok(frame) => frames.push(frame),
+                Err(err) if index + 1 == lines.len() => {
+                    warn!("skipping the incomplete last recorded frame: {}", err);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(Self { frames })
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        for frame in &self.frames {
+            write_frame(&mut writer, frame)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_frame(mut writer: impl Write, frame: &RecordedFrame) -> anyhow::Result<()> {
+    writeln!(writer, "{}", ron::ser::to_string(frame)?)?;
+    Ok(())
+}
+
+/// streams recorded frames to `path` while recording
+#[derive(Resource)]
+pub struct XrInputRecorder {
+    pub path: PathBuf,
+    pub flush_interval: Duration,
+    writer: Option<BufWriter<File>>,
+    frames: usize,
+    since_flush: Duration,
+}
+
+impl XrInputRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            flush_interval: Duration::from_secs(1),
+            writer: None,
+            frames: 0,
+            since_flush: Duration::ZERO,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// the frames recorded since the last start
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// starts a new recording, an existing file at `path` is replaced
+    pub fn start(&mut self) -> anyhow::Result<()> {
+        self.stop()?;
+        self.writer = Some(BufWriter::new(File::create(&self.path)?));
+        self.frames = 0;
+        self.since_flush = Duration::ZERO;
+        Ok(())
+    }
+
+    /// saves the recorded frames and closes the file
+    pub fn stop(&mut self) -> anyhow::Result<()> {
+        self.save()?;
+        self.writer = None;
+        Ok(())
+    }
+
+    /// writes the buffered frames to the file, recording continues
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        if let Some(writer) = &mut self.writer {
+            writer.flush()?;
+        }
+        self.since_flush = Duration::ZERO;
+        Ok(())
+    }
+
+    /// appends `frame` and saves once `flush_interval` passed, does nothing while stopped
+    pub fn record(&mut self, frame: &RecordedFrame, delta: Duration) -> anyhow::Result<()> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        write_frame(writer, frame)?;
+        self.frames += 1;
+        self.since_flush += delta;
+        if self.since_flush >= self.flush_interval {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+pub fn start_recording(mut recorder: ResMut<XrInputRecorder>) {
+    if let Err(err) = recorder.start() {
+        error!(
+            "failed to start recording xr input to {:?}: {}",
+            recorder.path, err
+        );
+    }
+}
+
+pub fn record_xr_input(
+    mut recorder: ResMut<XrInputRecorder>,
+    time: Res<Time>,
+    frame_state: Res<XrFrameState>,
+    views: Res<XrViews>,
+    controller_query: Query<
+        (
+            &Transform,
+            Option<&AimPose>,
+            &XrControllerInput,
+            Has<OpenXRLeftController>,
+        ),
+        With<OpenXRController>,
+    >,
+) {
+    if !recorder.is_recording() {
+        return;
+    }
+    //lock frame
+    let frame_state = *frame_state.lock().unwrap();
+    let views = views
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|view| RecordedView {
+            pose: view.pose.into(),
+            fov: [
+                view.fov.angle_left,
+                view.fov.angle_right,
+                view.fov.angle_up,
+                view.fov.angle_down,
+            ],
+        })
+        .collect();
+    let mut frame = RecordedFrame {
+        predicted_display_time: frame_state.predicted_display_time.as_nanos(),
+        views,
+        ..default()
+    };
+    for (grip, aim, input, is_left) in controller_query.iter() {
+        let aim = aim.map(|aim| aim.0).unwrap_or(*grip);
+        let state = RecordedControllerState::from_input(*grip, aim, input);
+        if is_left {
+            frame.left = state;
+            frame.menu_button = input.menu_button;
+        } else {
+            frame.right = state;
+        }
+    }
+    if let Err(err) = recorder.record(&frame, time.delta()) {
+        error!("failed to record xr input, recording stopped: {}", err);
+        recorder.writer = None;
+    }
+}
+
+pub fn stop_recording_on_exit(
+    mut recorder: ResMut<XrInputRecorder>,
+    mut exit: EventReader<AppExit>,
+) {
+    if exit.read().next().is_none() || !recorder.is_recording() {
+        return;
+    }
+    match recorder.stop() {
+        Ok(_) => info!(
+            "saved {} recorded xr frames to {:?}",
+            recorder.frames(),
+            recorder.path
+        ),
+        Err(err) => error!("failed to save xr input recording: {}", err),
+    }
+}
+
+#[derive(Resource)]
+pub struct XrInputReplay {
+    pub recording: XrInputRecording,
+    /// index of the next frame to replay
+    pub frame: usize,
+    /// how far the replay is, relative to the display time of the first frame
+    pub elapsed: Duration,
+    pub looping: bool,
+    pub playing: bool,
+}
+
+impl XrInputReplay {
+    pub fn new(recording: XrInputRecording, looping: bool) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            elapsed: Duration::ZERO,
+            looping,
+            playing: true,
+        }
+    }
+
+    /// the frame that was replayed last
+    pub fn current_frame(&self) -> Option<&RecordedFrame> {
+        self.recording.frames.get(self.frame.checked_sub(1)?)
+    }
+
+    /// the display time of `frame` relative to the first frame
+    pub fn frame_time(&self, frame: usize) -> Duration {
+        let first = self.recording.frames[0].predicted_display_time;
+        let time = self.recording.frames[frame].predicted_display_time - first;
+        Duration::from_nanos(time.max(0) as u64)
+    }
+
+    /// the display time of the last frame relative to the first one
+    pub fn duration(&self) -> Duration {
+        match self.recording.frames.len() {
+            0 => Duration::ZERO,
+            frames => self.frame_time(frames - 1),
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        !self.looping && self.elapsed > self.duration()
+    }
+
+    /// moves the replay `delta` forward, the first call replays the first frame, returns whether
+    /// there is a frame to replay
+    pub fn advance(&mut self, delta: Duration) -> bool {
+        if !self.playing || self.recording.frames.is_empty() {
+            return false;
+        }
+        if self.frame > 0 {
+            self.elapsed += delta;
+        }
+        if self.elapsed > self.duration() {
+            if !self.looping {
+                self.playing = false;
+                info!("xr input replay finished");
+                return false;
+            }
+            let duration = self.duration().as_nanos();
+            self.elapsed = if duration > 0 {
+                Duration::from_nanos((self.elapsed.as_nanos() % duration) as u64)
+            } else {
+                Duration::ZERO
+            };
+            self.frame = 0;
+        }
+        while self.frame < self.recording.frames.len()
+            && self.frame_time(self.frame) <= self.elapsed
+        {
+            self.frame += 1;
+        }
+        true
+    }
+}
+
+/// advances the replay by the time since the last update and replays the controller input
+pub fn replay_xr_input(
+    mut replay: ResMut<XrInputReplay>,
+    time: Res<Time>,
+    mut controller_query: Query<
+        (&mut XrControllerInput, Has<OpenXRLeftController>),
+        With<OpenXRController>,
+    >,
+) {
+    if !replay.advance(time.delta()) {
+        return;
+    }
+    let frame = match replay.current_frame() {
+        Some(frame) => frame,
+        None => return,
+    };
+    for (mut input, is_left) in controller_query.iter_mut() {
+        *input = if is_left {
+            frame.controller_input(Hand::Left)
+        } else {
+            frame.controller_input(Hand::Right)
+        };
+    }
+}
+
+/// moves the head and controllers to the poses of the replayed frame
+pub fn replay_xr_poses(
+    replay: Res<XrInputReplay>,
+    mut head_query: Query<&mut Transform, With<XrSimulatedHead>>,
+    mut left_controller_query: Query<
+        (&mut Transform, Option<&mut AimPose>),
+        (
+            With<OpenXRLeftController>,
+            Without<OpenXRRightController>,
+            Without<XrSimulatedHead>,
+        ),
+    >,
+    mut right_controller_query: Query<
+        (&mut Transform, Option<&mut AimPose>),
+        (
+            With<OpenXRRightController>,
+            Without<OpenXRLeftController>,
+            Without<XrSimulatedHead>,
+        ),
+    >,
+) {
+    if !replay.playing {
+        return;
+    }
+    let frame = match replay.current_frame() {
+        Some(frame) => frame,
+        None => return,
+    };
+    if let Some(head) = frame.head_transform() {
+        for mut transform in head_query.iter_mut() {
+            *transform = head;
+        }
+    }
+    for (mut transform, aim) in left_controller_query.iter_mut() {
+        *transform = frame.left.grip.to_transform();
+        if let Some(mut aim) = aim {
+            *aim = AimPose(frame.left.aim.to_transform());
+        }
+    }
+    for (mut transform, aim) in right_controller_query.iter_mut() {
+        *transform = frame.right.grip.to_transform();
+        if let Some(mut aim) = aim {
+            *aim = AimPose(frame.right.aim.to_transform());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(hand: Hand) -> XrControllerInput {
+        XrControllerInput {
+            trigger: 0.75,
+            trigger_touched: true,
+            squeeze: 0.25,
+            primary_button: true,
+            primary_touched: true,
+            secondary_button: false,
+            secondary_touched: true,
+            menu_button: hand == Hand::Left,
+            thumbstick: Vec2::new(-0.5, 0.125),
+            thumbstick_click: false,
+            thumbstick_touched: true,
+            thumbrest_touched: false,
+            trigger_proximity: Some(true),
+            thumb_proximity: None,
+        }
+    }
+
+    fn frame() -> RecordedFrame {
+        let grip = Transform::from_xyz(0.1, 1.2, -0.3).with_rotation(Quat::from_rotation_y(0.5));
+        let aim = grip.with_rotation(Quat::from_rotation_x(-0.25));
+        RecordedFrame {
+            predicted_display_time: 123_456_789,
+            views: vec![RecordedView {
+                pose: grip.into(),
+                fov: [-0.8, 0.8, 0.7, -0.7],
+            }],
+            left: RecordedControllerState::from_input(grip, aim, &input(Hand::Left)),
+            right: RecordedControllerState::from_input(aim, grip, &input(Hand::Right)),
+            menu_button: true,
+        }
+    }
+
+    #[test]
+    fn controller_state_round_trips_through_ron() {
+        let recording = XrInputRecording {
+            frames: vec![frame(), RecordedFrame::default(), frame()],
+        };
+        let mut ron = Vec::new();
+        recording.write(&mut ron).unwrap();
+        let replayed = XrInputRecording::read(ron.as_slice()).unwrap();
+        assert_eq!(replayed, recording);
+        for hand in [Hand::Left, Hand::Right] {
+            assert_eq!(replayed.frames[0].controller_input(hand), input(hand));
+        }
+    }
+
+    fn replay(frame_times_ms: &[i64], looping: bool) -> XrInputReplay {
+        let frames = frame_times_ms
+            .iter()
+            .map(|time| RecordedFrame {
+                predicted_display_time: 5_000_000_000 + time * 1_000_000,
+                ..default()
+            })
+            .collect();
+        XrInputReplay::new(XrInputRecording { frames }, looping)
+    }
+
+    /// the replayed frame after every update
+    fn replayed_frames(replay: &mut XrInputReplay, delta_ms: u64, updates: usize) -> Vec<usize> {
+        (0..updates)
+            .map_while(|_| {
+                replay
+                    .advance(Duration::from_millis(delta_ms))
+                    .then(|| replay.frame - 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replay_follows_the_recorded_display_times() {
+        //recorded at 100hz
+        let times = [0, 10, 20, 30];
+        //replayed at 50hz frames are skipped
+        let mut fast = replay(&times, false);
+        assert_eq!(replayed_frames(&mut fast, 20, 10), vec![0, 2]);
+        assert!(fast.finished());
+        //replayed at 200hz frames are held
+        let mut slow = replay(&times, false);
+        assert_eq!(replayed_frames(&mut slow, 5, 10), vec![0, 0, 1, 1, 2, 2, 3]);
+        assert!(!slow.playing);
+    }
+
+    #[test]
+    fn looping_replay_wraps_around() {
+        //the last and the first frame share a display time once wrapped
+        let mut looping = replay(&[0, 10, 20, 30], true);
+        assert_eq!(replayed_frames(&mut looping, 20, 5), vec![0, 2, 1, 3, 2]);
+        assert!(!looping.finished());
+    }
+
+    #[test]
+    fn incomplete_last_frame_is_skipped() {
+        let recording = XrInputRecording {
+            frames: vec![frame(), frame()],
+        };
+        let mut ron = Vec::new();
+        recording.write(&mut ron).unwrap();
+        //a crash while the last frame was written
+        ron.truncate(ron.len() - 20);
+        let replayed = XrInputRecording::read(ron.as_slice()).unwrap();
+        assert_eq!(replayed.frames, vec![frame()]);
+    }
+}
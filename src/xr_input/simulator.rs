@@ -0,0 +1,406 @@
+use std::f32::consts::PI;
+
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+
+use super::buttons::XrButtonsPlugin;
+use super::controllers::{XrControllerInput, XrControllerInputSet};
+use super::haptics::XrHapticsPlugin;
+use super::trackers::{
+    adopt_open_xr_trackers, spawn_aim_entities, spawn_controller_trackers, update_aim_entities,
+    AimPose, OpenXRController, OpenXRHMD, OpenXRLeftController, OpenXRRightController,
+    OpenXRTrackingRoot, XrTrackerSettings,
+};
+use super::Hand;
+
+/// Simulates a headset and two controllers with the keyboard and mouse.
+///
+/// Use this with bevy's `DefaultPlugins` instead of `DefaultXrPlugins` when no headset is
+/// available. It drives the same controller trackers, `AimPose` and `XrControllerInput` as
+/// real hardware and renders the simulated head view to the normal window.
+///
+/// Default controls:
+/// - `WASD` move the head, `Q`/`E` move it down/up, hold the right mouse button to look around
+/// - hold left shift (left hand) or left control (right hand) to move and rotate that hand
+///   instead of the head
+/// - `T` or the left mouse button pulls the trigger, `G` squeezes the grip, `1`/`2` press the
+///   A/X and B/Y buttons and the arrow keys push the thumbstick of the selected hand (the right
+///   hand when no hand is selected)
+#[derive(Default)]
+pub struct XrSimulatorPlugin;
+
+impl Plugin for XrSimulatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<XrSimulatorConfig>()
+            .init_resource::<XrSimulatorState>()
+            .init_resource::<XrTrackerSettings>()
+            .add_plugins(XrButtonsPlugin)
+            //haptic events can be sent but there is nothing to vibrate
+            .add_plugins(XrHapticsPlugin)
+            .add_systems(Startup, setup_simulator)
+            .add_systems(PreUpdate, adopt_open_xr_trackers)
+            .add_systems(PreUpdate, spawn_aim_entities)
+            .add_systems(PreUpdate, simulate_xr_input.after(bevy::input::InputSystem))
+            .add_systems(
+                PreUpdate,
+                update_simulated_controller_input
+                    .in_set(XrControllerInputSet)
+                    .after(simulate_xr_input),
+            )
+            .add_systems(Update, update_simulated_trackers)
+            .add_systems(Update, update_aim_entities.after(update_simulated_trackers));
+    }
+}
+
+#[derive(Resource)]
+pub struct XrSimulatorConfig {
+    pub head_speed: f32,
+    pub hand_speed: f32,
+    pub mouse_sensitivity: f32,
+    pub forward: KeyCode,
+    pub back: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub look: MouseButton,
+    pub left_hand_modifier: KeyCode,
+    pub right_hand_modifier: KeyCode,
+    pub trigger: KeyCode,
+    pub trigger_mouse: MouseButton,
+    pub grip: KeyCode,
+    pub primary_button: KeyCode,
+    pub secondary_button: KeyCode,
+    pub thumbstick_up: KeyCode,
+    pub thumbstick_down: KeyCode,
+    pub thumbstick_left: KeyCode,
+    pub thumbstick_right: KeyCode,
+}
+
+impl Default for XrSimulatorConfig {
+    fn default() -> Self {
+        Self {
+            head_speed: 1.5,
+            hand_speed: 0.5,
+            mouse_sensitivity: 0.003,
+            forward: KeyCode::W,
+            back: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+            up: KeyCode::E,
+            down: KeyCode::Q,
+            look: MouseButton::Right,
+            left_hand_modifier: KeyCode::ShiftLeft,
+            right_hand_modifier: KeyCode::ControlLeft,
+            trigger: KeyCode::T,
+            trigger_mouse: MouseButton::Left,
+            grip: KeyCode::G,
+            primary_button: KeyCode::Key1,
+            secondary_button: KeyCode::Key2,
+            thumbstick_up: KeyCode::Up,
+            thumbstick_down: KeyCode::Down,
+            thumbstick_left: KeyCode::Left,
+            thumbstick_right: KeyCode::Right,
+        }
+    }
+}
+
+/// the simulated device state, poses are relative to the tracking root
+#[derive(Resource)]
+pub struct XrSimulatorState {
+    pub head_position: Vec3,
+    pub head_yaw: f32,
+    pub head_pitch: f32,
+    pub left: SimulatedController,
+    pub right: SimulatedController,
+}
+
+impl Default for XrSimulatorState {
+    fn default() -> Self {
+        Self {
+            head_position: Vec3::new(0.0, 1.6, 0.0),
+            head_yaw: 0.0,
+            head_pitch: 0.0,
+            left: SimulatedController::new(Vec3::new(-0.2, -0.3, -0.4)),
+            right: SimulatedController::new(Vec3::new(0.2, -0.3, -0.4)),
+        }
+    }
+}
+
+impl XrSimulatorState {
+    pub fn head_rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.head_yaw, self.head_pitch, 0.0)
+    }
+
+    pub fn head_transform(&self) -> Transform {
+        Transform {
+            translation: self.head_position,
+            rotation: self.head_rotation(),
+            ..default()
+        }
+    }
+
+    pub fn controller(&self, hand: Hand) -> &SimulatedController {
+        match hand {
+            Hand::Left => &self.left,
+            Hand::Right => &self.right,
+        }
+    }
+
+    pub fn controller_mut(&mut self, hand: Hand) -> &mut SimulatedController {
+        match hand {
+            Hand::Left => &mut self.left,
+            Hand::Right => &mut self.right,
+        }
+    }
+
+    /// controllers follow the head yaw so they stay in front of the user
+    pub fn controller_transform(&self, hand: Hand) -> Transform {
+        let controller = self.controller(hand);
+        let yaw = Quat::from_rotation_y(self.head_yaw);
+        Transform {
+            translation: self.head_position + yaw.mul_vec3(controller.offset),
+            rotation: yaw.mul_quat(controller.rotation),
+            ..default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SimulatedController {
+    /// offset from the head in the head's yaw space
+    pub offset: Vec3,
+    pub rotation: Quat,
+    pub trigger: f32,
+    pub grip: f32,
+    pub primary_button: bool,
+    pub secondary_button: bool,
+    pub thumbstick: Vec2,
+}
+
+impl SimulatedController {
+    pub fn new(offset: Vec3) -> Self {
+        Self {
+            offset,
+            rotation: Quat::IDENTITY,
+            trigger: 0.0,
+            grip: 0.0,
+            primary_button: false,
+            secondary_button: false,
+            thumbstick: Vec2::ZERO,
+        }
+    }
+
+    pub fn controller_input(&self) -> XrControllerInput {
+        XrControllerInput {
+            trigger: self.trigger,
+            trigger_touched: self.trigger > 0.0,
+            squeeze: self.grip,
+            primary_button: self.primary_button,
+            primary_touched: self.primary_button,
+            secondary_button: self.secondary_button,
+            secondary_touched: self.secondary_button,
+            menu_button: false,
+            thumbstick: self.thumbstick,
+            thumbstick_click: false,
+            thumbstick_touched: self.thumbstick != Vec2::ZERO,
+            thumbrest_touched: false,
+            trigger_proximity: None,
+            thumb_proximity: None,
+        }
+    }
+}
+
+/// marks the camera that stands in for the headset, it is also marked as the `OpenXRHMD`
+#[derive(Component)]
+pub struct XrSimulatedHead;
+
+fn setup_simulator(
+    mut commands: Commands,
+    state: Res<XrSimulatorState>,
+    tracker_settings: Res<XrTrackerSettings>,
+) {
+    let tracking_root = commands
+        .spawn((SpatialBundle::default(), OpenXRTrackingRoot))
+        .id();
+    let head = commands
+        .spawn((
+            Camera3dBundle {
+                transform: state.head_transform(),
+                ..default()
+            },
+            XrSimulatedHead,
+            OpenXRHMD,
+        ))
+        .id();
+    commands.entity(tracking_root).add_child(head);
+    spawn_controller_trackers(&mut commands, tracking_root, &tracker_settings);
+}
+
+pub fn simulate_xr_input(
+    time: Res<Time>,
+    config: Res<XrSimulatorConfig>,
+    mut state: ResMut<XrSimulatorState>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+) {
+    let selected_hand = match (
+        keys.pressed(config.left_hand_modifier),
+        keys.pressed(config.right_hand_modifier),
+    ) {
+        (true, _) => Some(Hand::Left),
+        (false, true) => Some(Hand::Right),
+        (false, false) => None,
+    };
+
+    //movement input, x is right, y is up and z is back like everywhere else in bevy
+    let mut movement = Vec3::ZERO;
+    if keys.pressed(config.forward) {
+        movement.z -= 1.0;
+    }
+    if keys.pressed(config.back) {
+        movement.z += 1.0;
+    }
+    if keys.pressed(config.left) {
+        movement.x -= 1.0;
+    }
+    if keys.pressed(config.right) {
+        movement.x += 1.0;
+    }
+    if keys.pressed(config.up) {
+        movement.y += 1.0;
+    }
+    if keys.pressed(config.down) {
+        movement.y -= 1.0;
+    }
+    let movement = movement.normalize_or_zero() * time.delta_seconds();
+
+    let mut look = Vec2::ZERO;
+    for motion in mouse_motion.read() {
+        look += motion.delta;
+    }
+    if !mouse_buttons.pressed(config.look) {
+        look = Vec2::ZERO;
+    }
+    let look = look * config.mouse_sensitivity;
+
+    match selected_hand {
+        Some(hand) => {
+            let controller = state.controller_mut(hand);
+            controller.offset += movement * config.hand_speed;
+            controller.rotation = Quat::from_rotation_y(-look.x)
+                .mul_quat(controller.rotation)
+                .mul_quat(Quat::from_rotation_x(-look.y));
+        }
+        None => {
+            let yaw = Quat::from_rotation_y(state.head_yaw);
+            state.head_position += yaw.mul_vec3(movement * config.head_speed);
+            state.head_yaw -= look.x;
+            state.head_pitch = (state.head_pitch - look.y).clamp(-PI * 0.49, PI * 0.49);
+        }
+    }
+
+    //buttons go to the selected hand, or the right hand if nothing is selected
+    let button_hand = selected_hand.unwrap_or(Hand::Right);
+    let trigger_pulled =
+        keys.pressed(config.trigger) || mouse_buttons.pressed(config.trigger_mouse);
+    let mut thumbstick = Vec2::ZERO;
+    if keys.pressed(config.thumbstick_up) {
+        thumbstick.y += 1.0;
+    }
+    if keys.pressed(config.thumbstick_down) {
+        thumbstick.y -= 1.0;
+    }
+    if keys.pressed(config.thumbstick_left) {
+        thumbstick.x -= 1.0;
+    }
+    if keys.pressed(config.thumbstick_right) {
+        thumbstick.x += 1.0;
+    }
+    for hand in [Hand::Left, Hand::Right] {
+        let active = hand == button_hand;
+        let controller = state.controller_mut(hand);
+        controller.trigger = if active && trigger_pulled { 1.0 } else { 0.0 };
+        controller.grip = if active && keys.pressed(config.grip) {
+            1.0
+        } else {
+            0.0
+        };
+        controller.primary_button = active && keys.pressed(config.primary_button);
+        controller.secondary_button = active && keys.pressed(config.secondary_button);
+        controller.thumbstick = if active {
+            thumbstick.normalize_or_zero()
+        } else {
+            Vec2::ZERO
+        };
+    }
+}
+
+pub fn update_simulated_controller_input(
+    mut commands: Commands,
+    state: Res<XrSimulatorState>,
+    mut controller_query: Query<
+        (
+            Entity,
+            Option<&mut XrControllerInput>,
+            Has<OpenXRLeftController>,
+            Has<OpenXRRightController>,
+        ),
+        With<OpenXRController>,
+    >,
+) {
+    for (entity, input, left, right) in controller_query.iter_mut() {
+        let hand = match (left, right) {
+            (true, _) => Hand::Left,
+            (false, true) => Hand::Right,
+            (false, false) => continue,
+        };
+        let new_input = state.controller(hand).controller_input();
+        match input {
+            Some(mut input) => *input = new_input,
+            None => {
+                commands.entity(entity).insert(new_input);
+            }
+        }
+    }
+}
+
+pub fn update_simulated_trackers(
+    state: Res<XrSimulatorState>,
+    mut head_query: Query<&mut Transform, With<XrSimulatedHead>>,
+    mut left_controller_query: Query<
+        (&mut Transform, Option<&mut AimPose>),
+        (
+            With<OpenXRLeftController>,
+            Without<OpenXRRightController>,
+            Without<XrSimulatedHead>,
+        ),
+    >,
+    mut right_controller_query: Query<
+        (&mut Transform, Option<&mut AimPose>),
+        (
+            With<OpenXRRightController>,
+            Without<OpenXRLeftController>,
+            Without<XrSimulatedHead>,
+        ),
+    >,
+) {
+    for mut transform in head_query.iter_mut() {
+        *transform = state.head_transform();
+    }
+    let left = state.controller_transform(Hand::Left);
+    for (mut transform, aim) in left_controller_query.iter_mut() {
+        *transform = left;
+        if let Some(mut aim) = aim {
+            *aim = AimPose(left);
+        }
+    }
+    let right = state.controller_transform(Hand::Right);
+    for (mut transform, aim) in right_controller_query.iter_mut() {
+        *transform = right;
+        if let Some(mut aim) = aim {
+            *aim = AimPose(right);
+        }
+    }
+}
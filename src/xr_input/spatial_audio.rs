@@ -0,0 +1,75 @@
+use bevy::audio::SpatialListener;
+use bevy::prelude::*;
+
+use crate::resources::XrViews;
+
+use super::{
+    trackers::{update_open_xr_hmd, OpenXRHMD},
+    Vec3Conv,
+};
+
+/// Puts bevy's [`SpatialListener`] on the `OpenXRHMD` entity so spatial audio follows the head.
+///
+/// The ears are placed on the eyes' offsets from the head, so the listener uses the user's actual
+/// IPD. Without views, e.g. in the simulator, the listener keeps `default_gap`.
+pub struct XrSpatialAudioPlugin {
+    /// distance between the ears in meters until the views are known
+    pub default_gap: f32,
+}
+
+impl Default for XrSpatialAudioPlugin {
+    fn default() -> Self {
+        Self { default_gap: 0.064 }
+    }
+}
+
+impl Plugin for XrSpatialAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(XrSpatialAudioSettings {
+            default_gap: self.default_gap,
+        })
+        .add_systems(PreUpdate, add_hmd_spatial_listener)
+        .add_systems(
+            PreUpdate,
+            update_hmd_spatial_listener
+                .run_if(resource_exists::<XrViews>())
+                .after(update_open_xr_hmd),
+        );
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct XrSpatialAudioSettings {
+    pub default_gap: f32,
+}
+
+pub fn add_hmd_spatial_listener(
+    mut commands: Commands,
+    settings: Res<XrSpatialAudioSettings>,
+    hmd_query: Query<Entity, (With<OpenXRHMD>, Without<SpatialListener>)>,
+) {
+    for hmd in hmd_query.iter() {
+        commands
+            .entity(hmd)
+            .insert(SpatialListener::new(settings.default_gap));
+    }
+}
+
+/// moves the ears onto the eye positions, relative to the hmd
+pub fn update_hmd_spatial_listener(
+    views: Res<XrViews>,
+    mut hmd_query: Query<(&Transform, &mut SpatialListener), With<OpenXRHMD>>,
+) {
+    let views = views.lock().unwrap();
+    let (left, right) = match (views.first(), views.get(1)) {
+        (Some(left), Some(right)) => (left, right),
+        _ => return,
+    };
+    for (head, mut listener) in hmd_query.iter_mut() {
+        let head_inverse = head.rotation.inverse();
+        //only the sideways offset, ears sit level with the head's center rather than the eyes
+        let ear = |eye: Vec3| Vec3::X * (head_inverse * (eye - head.translation)).x;
+        listener.left_ear_offset = ear(left.pose.position.to_vec3());
+        listener.right_ear_offset = ear(right.pose.position.to_vec3());
+    }
+}
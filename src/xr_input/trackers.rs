@@ -1,14 +1,18 @@
 use bevy::prelude::{
-    info, Added, BuildChildren, Commands, Component, Entity, Query, Res, Transform, Vec3, With,
-    Without,
+    info, warn, Added, BuildChildren, Bundle, Children, Commands, Component, Entity, Event,
+    EventWriter, Has, Or, Query, Res, ResMut, Resource, SpatialBundle, Transform, Vec3, Visibility,
+    With, Without,
 };
+use openxr::{SpaceLocation, SpaceLocationFlags, SpaceVelocity, SpaceVelocityFlags};
 
 use crate::{
     input::XrInput,
     resources::{XrFrameState, XrInstance, XrSession},
 };
 
-use super::{oculus_touch::OculusController, Hand, QuatConv, Vec3Conv};
+use super::{
+    controllers::XrControllerInput, oculus_touch::OculusController, Hand, QuatConv, Vec3Conv,
+};
 
 #[derive(Component)]
 pub struct OpenXRTrackingRoot;
@@ -28,6 +32,208 @@ pub struct OpenXRRightController;
 pub struct OpenXRController;
 #[derive(Component)]
 pub struct AimPose(pub Transform);
+/// the pinch point between thumb and index of a tracked hand, from `XR_EXT_hand_interaction`
+#[derive(Component)]
+pub struct PinchPose(pub Transform);
+/// the index fingertip of a tracked hand, from `XR_EXT_hand_interaction`
+#[derive(Component)]
+pub struct PokePose(pub Transform);
+/// the center of the palm from `XR_EXT_palm_pose`, `None` until the runtime located it once
+#[derive(Component, Default)]
+pub struct PalmPose(pub Option<Transform>);
+/// a child of a controller that follows its `AimPose`, see `XrTrackerSettings::aim_entities`
+#[derive(Component)]
+pub struct OpenXRAim;
+
+/// which tracker entities the input plugins spawn, insert it to change the defaults
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct XrTrackerSettings {
+    /// spawn an `XrTrackerBundle` for each controller under the tracking root, turn this off when
+    /// spawning the bundles yourself
+    pub spawn_controllers: bool,
+    /// give every controller an `OpenXRAim` child so content can be attached to the aim pose
+    pub aim_entities: bool,
+}
+
+impl Default for XrTrackerSettings {
+    fn default() -> Self {
+        Self {
+            spawn_controllers: true,
+            aim_entities: false,
+        }
+    }
+}
+
+/// a controller tracker, add your own components next to it to attach content to a controller
+#[derive(Bundle)]
+pub struct XrTrackerBundle<M: Component> {
+    pub spatial: SpatialBundle,
+    pub tracker: OpenXRTracker,
+    pub controller: OpenXRController,
+    pub hand: M,
+    pub aim: AimPose,
+    pub pinch: PinchPose,
+    pub poke: PokePose,
+    pub palm: PalmPose,
+    pub velocity: XrVelocity,
+}
+
+impl<M: Component> XrTrackerBundle<M> {
+    pub fn new(hand: M) -> Self {
+        Self {
+            spatial: SpatialBundle::default(),
+            tracker: OpenXRTracker,
+            controller: OpenXRController,
+            hand,
+            aim: AimPose(Transform::default()),
+            pinch: PinchPose(Transform::default()),
+            poke: PokePose(Transform::default()),
+            palm: PalmPose::default(),
+            velocity: XrVelocity::default(),
+        }
+    }
+
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.spatial.visibility = visibility;
+        self
+    }
+}
+
+impl XrTrackerBundle<OpenXRLeftController> {
+    pub fn left_controller() -> Self {
+        Self::new(OpenXRLeftController)
+    }
+}
+
+impl XrTrackerBundle<OpenXRRightController> {
+    pub fn right_controller() -> Self {
+        Self::new(OpenXRRightController)
+    }
+}
+
+/// the runtime's velocity estimate of a controller's grip in tracking root space, `None` when the
+/// runtime doesn't report a valid velocity
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct XrVelocity {
+    /// meters per second
+    pub linear: Option<Vec3>,
+    /// axis scaled by the angular speed in radians per second
+    pub angular: Option<Vec3>,
+}
+
+impl From<SpaceVelocity> for XrVelocity {
+    fn from(velocity: SpaceVelocity) -> Self {
+        Self {
+            linear: if velocity
+                .velocity_flags
+                .contains(SpaceVelocityFlags::LINEAR_VALID)
+            {
+                Some(velocity.linear_velocity.to_vec3())
+            } else {
+                None
+            },
+            angular: if velocity
+                .velocity_flags
+                .contains(SpaceVelocityFlags::ANGULAR_VALID)
+            {
+                Some(velocity.angular_velocity.to_vec3())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl From<openxr::sys::HandJointVelocityEXT> for XrVelocity {
+    fn from(velocity: openxr::sys::HandJointVelocityEXT) -> Self {
+        Self::from(SpaceVelocity {
+            velocity_flags: velocity.velocity_flags,
+            linear_velocity: velocity.linear_velocity,
+            angular_velocity: velocity.angular_velocity,
+        })
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct XrTrackingState {
+    pub position_valid: bool,
+    pub position_tracked: bool,
+    pub orientation_valid: bool,
+    pub orientation_tracked: bool,
+}
+
+impl XrTrackingState {
+    /// a device counts as tracked while both its position and orientation are valid
+    pub fn is_valid(&self) -> bool {
+        self.position_valid && self.orientation_valid
+    }
+}
+
+impl From<SpaceLocationFlags> for XrTrackingState {
+    fn from(flags: SpaceLocationFlags) -> Self {
+        Self {
+            position_valid: flags.contains(SpaceLocationFlags::POSITION_VALID),
+            position_tracked: flags.contains(SpaceLocationFlags::POSITION_TRACKED),
+            orientation_valid: flags.contains(SpaceLocationFlags::ORIENTATION_VALID),
+            orientation_tracked: flags.contains(SpaceLocationFlags::ORIENTATION_TRACKED),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum XrTrackedDevice {
+    Head,
+    LeftController,
+    RightController,
+}
+
+impl From<Hand> for XrTrackedDevice {
+    fn from(hand: Hand) -> Self {
+        match hand {
+            Hand::Left => XrTrackedDevice::LeftController,
+            Hand::Right => XrTrackedDevice::RightController,
+        }
+    }
+}
+
+/// sent when a device's position or orientation stops being valid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Event)]
+pub struct XrTrackingLost {
+    pub device: XrTrackedDevice,
+}
+
+/// sent when a lost device is valid again, and when a device is tracked for the first time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Event)]
+pub struct XrTrackingRegained {
+    pub device: XrTrackedDevice,
+}
+
+/// the latest tracking state of every device, written by the systems that locate the devices so
+/// it matches the applied poses, also mirrored onto the tracker entities
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct XrTrackingStates {
+    pub head: XrTrackingState,
+    pub left: XrTrackingState,
+    pub right: XrTrackingState,
+}
+
+impl XrTrackingStates {
+    pub fn get(&self, device: XrTrackedDevice) -> XrTrackingState {
+        match device {
+            XrTrackedDevice::Head => self.head,
+            XrTrackedDevice::LeftController => self.left,
+            XrTrackedDevice::RightController => self.right,
+        }
+    }
+
+    pub fn get_mut(&mut self, device: XrTrackedDevice) -> &mut XrTrackingState {
+        match device {
+            XrTrackedDevice::Head => &mut self.head,
+            XrTrackedDevice::LeftController => &mut self.left,
+            XrTrackedDevice::RightController => &mut self.right,
+        }
+    }
+}
 
 pub fn adopt_open_xr_trackers(
     query: Query<Entity, Added<OpenXRTracker>>,
@@ -47,20 +253,80 @@ pub fn adopt_open_xr_trackers(
     }
 }
 
+/// spawns the controller trackers under `root` unless `XrTrackerSettings::spawn_controllers` is off
+pub fn spawn_controller_trackers(
+    commands: &mut Commands,
+    root: Entity,
+    settings: &XrTrackerSettings,
+) {
+    if !settings.spawn_controllers {
+        return;
+    }
+    let left = commands.spawn(XrTrackerBundle::left_controller()).id();
+    let right = commands.spawn(XrTrackerBundle::right_controller()).id();
+    commands.entity(root).push_children(&[left, right]);
+}
+
+/// gives new controllers an `OpenXRAim` child when `XrTrackerSettings::aim_entities` is on
+pub fn spawn_aim_entities(
+    mut commands: Commands,
+    settings: Res<XrTrackerSettings>,
+    controller_query: Query<(Entity, Has<AimPose>), Added<OpenXRController>>,
+) {
+    if !settings.aim_entities {
+        return;
+    }
+    for (controller, has_aim_pose) in controller_query.iter() {
+        let mut controller = commands.entity(controller);
+        //the child follows the aim pose, so make sure there is one to follow
+        if !has_aim_pose {
+            controller.insert(AimPose(Transform::default()));
+        }
+        controller.with_children(|parent| {
+            parent.spawn((SpatialBundle::default(), OpenXRAim));
+        });
+    }
+}
+
+/// moves the `OpenXRAim` children onto their controller's `AimPose`
+pub fn update_aim_entities(
+    controller_query: Query<(&Transform, &AimPose, &Children), With<OpenXRController>>,
+    mut aim_query: Query<&mut Transform, (With<OpenXRAim>, Without<OpenXRController>)>,
+) {
+    for (grip, aim, children) in controller_query.iter() {
+        //the aim pose is in tracking root space like the grip, the child is relative to the grip
+        let grip_inverse = grip.rotation.inverse();
+        let local = Transform {
+            translation: grip_inverse * (aim.0.translation - grip.translation),
+            rotation: grip_inverse * aim.0.rotation,
+            ..aim.0
+        };
+        let mut aims = aim_query.iter_many_mut(children);
+        while let Some(mut transform) = aims.fetch_next() {
+            *transform = local;
+        }
+    }
+}
+
+/// moves the controller trackers to their located poses and writes the grip velocity and tracking
+/// state located with them, controllers without an `XrVelocity` get one
 pub fn update_open_xr_controllers(
+    mut commands: Commands,
+    mut states: ResMut<XrTrackingStates>,
     oculus_controller: Res<OculusController>,
-    mut left_controller_query: Query<(
-        &mut Transform,
-        Option<&mut AimPose>,
-        With<OpenXRLeftController>,
-        Without<OpenXRRightController>,
-    )>,
-    mut right_controller_query: Query<(
-        &mut Transform,
-        Option<&mut AimPose>,
-        With<OpenXRRightController>,
-        Without<OpenXRLeftController>,
-    )>,
+    mut controller_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            Option<&mut XrVelocity>,
+            Option<&mut AimPose>,
+            Option<&mut PinchPose>,
+            Option<&mut PokePose>,
+            Option<&mut PalmPose>,
+            Has<OpenXRLeftController>,
+        ),
+        Or<(With<OpenXRLeftController>, With<OpenXRRightController>)>,
+    >,
     frame_state: Res<XrFrameState>,
     instance: Res<XrInstance>,
     xr_input: Res<XrInput>,
@@ -70,53 +336,190 @@ pub fn update_open_xr_controllers(
     let frame_state = *frame_state.lock().unwrap();
     //get controller
     let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
-    //get left controller
     let left_grip_space = controller.grip_space(Hand::Left);
     let left_aim_space = controller.aim_space(Hand::Left);
-    let left_postion = left_grip_space.0.pose.position.to_vec3();
-    let left_aim_pose = left_controller_query.get_single_mut().unwrap().1;
-    match left_aim_pose {
-        Some(mut pose) => {
-            *pose = AimPose(Transform {
-                translation: left_aim_space.0.pose.position.to_vec3(),
-                rotation: left_aim_space.0.pose.orientation.to_quat(),
-                scale: Vec3::splat(1.0),
-            });
-        }
-        None => (),
-    }
-
-    left_controller_query
-        .get_single_mut()
-        .unwrap()
-        .0
-        .translation = left_postion;
-
-    left_controller_query.get_single_mut().unwrap().0.rotation =
-        left_grip_space.0.pose.orientation.to_quat();
-    //get right controller
     let right_grip_space = controller.grip_space(Hand::Right);
     let right_aim_space = controller.aim_space(Hand::Right);
-    let right_postion = right_grip_space.0.pose.position.to_vec3();
+    states.left = left_grip_space.0.location_flags.into();
+    states.right = right_grip_space.0.location_flags.into();
+    let hand_spaces = |hand: Hand| {
+        (
+            controller.pinch_space(hand).0,
+            controller.poke_space(hand).0,
+            controller.palm_space(hand).0,
+        )
+    };
+    let left_hand_spaces = hand_spaces(Hand::Left);
+    let right_hand_spaces = hand_spaces(Hand::Right);
+    //any number of entities can track the same controller
+    for (entity, mut transform, velocity, aim_pose, pinch_pose, poke_pose, palm_pose, is_left) in
+        controller_query.iter_mut()
+    {
+        let (grip_space, aim_space, (pinch_space, poke_space, palm_space)) = if is_left {
+            (&left_grip_space, &left_aim_space, &left_hand_spaces)
+        } else {
+            (&right_grip_space, &right_aim_space, &right_hand_spaces)
+        };
+        apply_location(&mut transform, &grip_space.0);
+        match velocity {
+            Some(mut velocity) => *velocity = grip_space.1.into(),
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(XrVelocity::from(grip_space.1));
+            }
+        }
+        if let Some(mut pose) = aim_pose {
+            apply_location(&mut pose.0, &aim_space.0);
+        }
+        if let Some(mut pose) = pinch_pose {
+            apply_location(&mut pose.0, pinch_space);
+        }
+        if let Some(mut pose) = poke_pose {
+            apply_location(&mut pose.0, poke_space);
+        }
+        //the palm is only used once the runtime located it completely
+        if let Some(mut pose) = palm_pose {
+            if palm_space.location_flags.contains(
+                SpaceLocationFlags::POSITION_VALID | SpaceLocationFlags::ORIENTATION_VALID,
+            ) {
+                let mut palm = Transform::default();
+                apply_location(&mut palm, palm_space);
+                pose.0 = Some(palm);
+            }
+        }
+    }
+}
+
+/// copies the valid parts of a located pose, invalid parts keep the last good value so lost
+/// devices don't snap to the origin
+pub fn apply_location(transform: &mut Transform, location: &SpaceLocation) {
+    if location
+        .location_flags
+        .contains(SpaceLocationFlags::POSITION_VALID)
+    {
+        transform.translation = location.pose.position.to_vec3();
+    }
+    if location
+        .location_flags
+        .contains(SpaceLocationFlags::ORIENTATION_VALID)
+    {
+        transform.rotation = location.pose.orientation.to_quat();
+    }
+}
 
-    let right_aim_pose = right_controller_query.get_single_mut().unwrap().1;
-    match right_aim_pose {
-        Some(mut pose) => {
-            *pose = AimPose(Transform {
-                translation: right_aim_space.0.pose.position.to_vec3(),
-                rotation: right_aim_space.0.pose.orientation.to_quat(),
-                scale: Vec3::splat(1.0),
-            });
+/// moves the `OpenXRHMD` entity to the located VIEW space, the eye cameras are its children
+pub fn update_open_xr_hmd(
+    mut hmd_query: Query<(&mut Transform, Option<&mut XrVelocity>), With<OpenXRHMD>>,
+    mut states: ResMut<XrTrackingStates>,
+    frame_state: Res<XrFrameState>,
+    xr_input: Res<XrInput>,
+) {
+    let frame_state = *frame_state.lock().unwrap();
+    let (location, velocity) = match xr_input
+        .head
+        .relate(&xr_input.stage, frame_state.predicted_display_time)
+    {
+        Ok(relation) => relation,
+        Err(err) => {
+            warn!("failed to locate the hmd: {}", err);
+            states.head = XrTrackingState::default();
+            return;
+        }
+    };
+    states.head = location.location_flags.into();
+    for (mut transform, xr_velocity) in hmd_query.iter_mut() {
+        apply_location(&mut transform, &location);
+        if let Some(mut xr_velocity) = xr_velocity {
+            *xr_velocity = velocity.into();
         }
-        None => (),
     }
+}
 
-    right_controller_query
-        .get_single_mut()
-        .unwrap()
-        .0
-        .translation = right_postion;
+/// sends the lost and regained events for the states located this frame and mirrors them onto the
+/// tracker entities
+pub fn update_tracking_states(
+    mut commands: Commands,
+    states: Res<XrTrackingStates>,
+    mut previous: Local<XrTrackingStates>,
+    mut lost: EventWriter<XrTrackingLost>,
+    mut regained: EventWriter<XrTrackingRegained>,
+    mut tracker_query: Query<
+        (
+            Entity,
+            Option<&mut XrTrackingState>,
+            Has<OpenXRHMD>,
+            Has<OpenXRLeftController>,
+            Has<OpenXRRightController>,
+        ),
+        Or<(With<OpenXRHMD>, With<OpenXRController>)>,
+    >,
+) {
+    for device in [
+        XrTrackedDevice::Head,
+        XrTrackedDevice::LeftController,
+        XrTrackedDevice::RightController,
+    ] {
+        match (
+            previous.get(device).is_valid(),
+            states.get(device).is_valid(),
+        ) {
+            (true, false) => lost.send(XrTrackingLost { device }),
+            (false, true) => regained.send(XrTrackingRegained { device }),
+            _ => {}
+        }
+    }
+    *previous = *states;
+    for (entity, tracking_state, hmd, left, right) in tracker_query.iter_mut() {
+        let device = match (hmd, left, right) {
+            (true, _, _) => XrTrackedDevice::Head,
+            (false, true, _) => XrTrackedDevice::LeftController,
+            (false, false, true) => XrTrackedDevice::RightController,
+            (false, false, false) => continue,
+        };
+        let state = states.get(device);
+        match tracking_state {
+            Some(mut tracking_state) => *tracking_state = state,
+            None => {
+                commands.entity(entity).insert(state);
+            }
+        }
+    }
+}
 
-    right_controller_query.get_single_mut().unwrap().0.rotation =
-        right_grip_space.0.pose.orientation.to_quat();
+/// caches the controller input on the controller entities once per frame
+pub fn update_xr_controller_input(
+    mut commands: Commands,
+    oculus_controller: Res<OculusController>,
+    mut controller_query: Query<
+        (
+            Entity,
+            Option<&mut XrControllerInput>,
+            Has<OpenXRLeftController>,
+            Has<OpenXRRightController>,
+        ),
+        With<OpenXRController>,
+    >,
+    frame_state: Res<XrFrameState>,
+    instance: Res<XrInstance>,
+    xr_input: Res<XrInput>,
+    session: Res<XrSession>,
+) {
+    let frame_state = *frame_state.lock().unwrap();
+    let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
+    let left = XrControllerInput::from_controller(&controller, Hand::Left);
+    let right = XrControllerInput::from_controller(&controller, Hand::Right);
+    for (entity, input, is_left, is_right) in controller_query.iter_mut() {
+        let new_input = match (is_left, is_right) {
+            (true, _) => left,
+            (false, true) => right,
+            (false, false) => continue,
+        };
+        match input {
+            Some(mut input) => *input = new_input,
+            None => {
+                commands.entity(entity).insert(new_input);
+            }
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::xr_input::trackers::OpenXRHMD;
 use crate::xr_input::{QuatConv, Vec3Conv};
 use crate::{LEFT_XR_TEXTURE_HANDLE, RIGHT_XR_TEXTURE_HANDLE};
 use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
@@ -220,10 +221,13 @@ impl CameraProjection for XRProjection {
 
 pub fn xr_camera_head_sync(
     views: ResMut<crate::resources::XrViews>,
-    mut query: Query<(&mut Transform, &XrCameraType, &mut XRProjection)>,
+    mut query: Query<(&mut Transform, &XrCameraType, &mut XRProjection), Without<OpenXRHMD>>,
+    hmd_query: Query<&Transform, With<OpenXRHMD>>,
 ) {
+    //the cameras are children of the hmd, so the eye poses are made relative to it
+    let head = hmd_query.get_single().copied().unwrap_or_default();
+    let head_inverse = head.rotation.inverse();
     let mut f = || -> Option<()> {
-        //TODO calculate HMD position
         for (mut transform, camera_type, mut xr_projection) in query.iter_mut() {
             let view_idx = match camera_type {
                 XrCameraType::Xr(eye) => *eye as usize,
@@ -232,8 +236,9 @@ pub fn xr_camera_head_sync(
             let v = views.lock().unwrap();
             let view = v.get(view_idx)?;
             xr_projection.fov = view.fov;
-            transform.rotation = view.pose.orientation.to_quat();
-            transform.translation = view.pose.position.to_vec3();
+            transform.translation =
+                head_inverse * (view.pose.position.to_vec3() - head.translation);
+            transform.rotation = head_inverse * view.pose.orientation.to_quat();
         }
         Some(())
     };